@@ -0,0 +1,162 @@
+use circllhist::Histogram;
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+
+// func BenchmarkHistogramMerge(b *testing.B) {
+fn bench_merge(c: &mut Criterion) {
+    let mut group = c.benchmark_group("merge");
+
+    // b.Run("random", func(b *testing.B) {
+    //     rand.New(rand.NewSource(time.Now().UnixNano()))
+    //     b.ReportAllocs()
+    //     for i := 0; i < b.N; i++ {
+    //         h1 := hist.New()
+    //         for i := 0; i < 500; i++ {
+    //             _ = h1.RecordIntScale(rand.Int63n(1000), 0)
+    //         }
+    //         h2 := hist.New()
+    //         for i := 0; i < 500; i++ {
+    //             _ = h2.RecordIntScale(rand.Int63n(1000), 0)
+    //         }
+    //         h1.Merge(h2)
+    //     }
+    // })
+    let mut rng = XorShift(0x2545_f491_4f6c_dd1d);
+    group.bench_function("random", |b| {
+        b.iter_batched(
+            || {
+                let mut h1 = Histogram::new();
+                let mut h2 = Histogram::new();
+                for _ in 0..500 {
+                    h1.record(rng.below(1000) as f64).unwrap();
+                    h2.record(rng.below(1000) as f64).unwrap();
+                }
+                (h1, h2)
+            },
+            |(mut h1, h2)| {
+                h1.merge_from(&h2);
+                black_box(h1)
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    // b.Run("large insert", func(b *testing.B) {
+    //     b.ReportAllocs()
+    //     for i := 0; i < b.N; i++ {
+    //         h1 := hist.New()
+    //         _ = h1.RecordIntScale(1, 0)
+    //         _ = h1.RecordIntScale(1000, 0)
+    //         h2 := hist.New()
+    //         for i := 10; i < 1000; i++ {
+    //             _ = h2.RecordIntScale(int64(i), 0)
+    //         }
+    //         h1.Merge(h2)
+    //     }
+    // })
+    group.bench_function("large insert", |b| {
+        b.iter_batched(
+            || {
+                let mut h1 = Histogram::new();
+                h1.record(1.0).unwrap();
+                h1.record(1000.0).unwrap();
+                let mut h2 = Histogram::new();
+                for i in 10..1000 {
+                    h2.record(i as f64).unwrap();
+                }
+                (h1, h2)
+            },
+            |(mut h1, h2)| {
+                h1.merge_from(&h2);
+                black_box(h1)
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    // two histograms with thousands of interleaved bins, so that every bin in
+    // one falls between two bins in the other.
+    group.bench_function("wide interleaved", |b| {
+        b.iter_batched(
+            || {
+                let mut h1 = Histogram::new();
+                let mut h2 = Histogram::new();
+                for exp in -20..20 {
+                    for digits in 10..100 {
+                        let h = if digits % 2 == 0 { &mut h1 } else { &mut h2 };
+                        h.record(digits as f64 * 10f64.powi(exp)).unwrap();
+                    }
+                }
+                (h1, h2)
+            },
+            |(mut h1, h2)| {
+                h1.merge_from(&h2);
+                black_box(h1)
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    group.finish();
+}
+
+fn bench_merge_all(c: &mut Criterion) {
+    let mut group = c.benchmark_group("merge_all");
+    let mut rng = XorShift(0x2545_f491_4f6c_dd1d);
+
+    // many histograms of values in the same range, with mostly overlapping
+    // bins.
+    let dense = (0..1000)
+        .map(|_| {
+            let mut h = Histogram::new();
+            for _ in 0..500 {
+                h.record(rng.below(1_000_000) as f64 / 1000.0).unwrap();
+            }
+            h
+        })
+        .collect::<Vec<_>>();
+    // many small histograms of values spanning 1e-9..1e9, with few
+    // overlapping bins.
+    let sparse = (0..5000)
+        .map(|_| {
+            let mut h = Histogram::new();
+            for _ in 0..20 {
+                let exp = rng.below(18) as i32 - 9;
+                h.record(rng.below(1000) as f64 * 10f64.powi(exp)).unwrap();
+            }
+            h
+        })
+        .collect::<Vec<_>>();
+
+    for (name, histograms) in [("dense", &dense), ("sparse", &sparse)] {
+        group.bench_function(format!("fold merge_from/{name}"), |b| {
+            b.iter(|| {
+                let mut merged = Histogram::new();
+                for h in histograms {
+                    merged.merge_from(h);
+                }
+                black_box(merged)
+            })
+        });
+
+        group.bench_function(format!("merge_all/{name}"), |b| {
+            b.iter(|| black_box(Histogram::merge_all(histograms)))
+        });
+    }
+
+    group.finish();
+}
+
+/// A small, deterministic PRNG, so that the benchmarks don't depend on `rand`.
+struct XorShift(u64);
+
+impl XorShift {
+    fn below(&mut self, n: u64) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0 % n
+    }
+}
+
+criterion_group!(benches, bench_merge, bench_merge_all);
+criterion_main!(benches);
@@ -0,0 +1,260 @@
+use crate::{
+    bin::{Bin, Bucket},
+    Histogram, RecordError, RecordPolicy,
+};
+use alloc::{boxed::Box, vec::Vec};
+use core::{
+    fmt, ptr,
+    sync::atomic::{AtomicPtr, AtomicU64, Ordering},
+};
+
+/// A histogram that can be recorded into concurrently through a shared
+/// reference.
+///
+/// Every bin has its own atomic counter, so recording a value never takes a
+/// lock. A reporter can read the recorded values with
+/// [`AtomicHistogram::snapshot`], or drain them with
+/// [`AtomicHistogram::snapshot_and_reset`], while other threads continue to
+/// record.
+///
+/// Counters are allocated lazily, one page per sign and exponent, so a
+/// histogram whose values span a few orders of magnitude only allocates a few
+/// pages.
+pub struct AtomicHistogram {
+    nan: AtomicU64,
+    zero: AtomicU64,
+    /// Pages of counters for non-zero bins, indexed by sign and exponent.
+    pages: Box<[AtomicPtr<Page>]>,
+    policy: RecordPolicy,
+}
+
+/// The counters for every bin with a given sign and exponent, indexed by the
+/// absolute value of the bin's value minus 10.
+struct Page([AtomicU64; PAGE_LEN]);
+
+/// The number of two-digit bin values.
+const PAGE_LEN: usize = 90;
+
+/// One page for each exponent, for each sign.
+const PAGES: usize = 2 * 256;
+
+enum Slot {
+    Nan,
+    Zero,
+    Page { page: usize, idx: usize },
+}
+
+impl AtomicHistogram {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_policy(RecordPolicy::default())
+    }
+
+    /// Returns a new histogram that records values according to `policy`.
+    ///
+    /// Snapshots of the histogram use the same policy.
+    #[must_use]
+    pub fn with_policy(policy: RecordPolicy) -> Self {
+        Self {
+            nan: AtomicU64::new(0),
+            zero: AtomicU64::new(0),
+            pages: (0..PAGES)
+                .map(|_| AtomicPtr::new(ptr::null_mut()))
+                .collect(),
+            policy,
+        }
+    }
+
+    /// Returns this histogram's [`RecordPolicy`].
+    #[must_use]
+    pub fn policy(&self) -> RecordPolicy {
+        self.policy
+    }
+
+    /// Records an integer scalar value.
+    pub fn record_int_scale(&self, val: i64, scale: i32) -> Result<&Self, RecordError> {
+        self.record_int_scales(val, scale, 1)
+    }
+
+    /// Records `n` occurances of an integer scalar value.
+    pub fn record_int_scales(&self, val: i64, scale: i32, n: u64) -> Result<&Self, RecordError> {
+        let bin = match self.policy {
+            RecordPolicy::Lenient => Bin::from_int_scale(val, scale),
+            RecordPolicy::Strict => Bin::try_from_int_scale(val, scale)?,
+        };
+        self.add(bin, n)?;
+        Ok(self)
+    }
+
+    /// Record a floating point value.
+    pub fn record(&self, val: f64) -> Result<&Self, RecordError> {
+        self.record_f64s(val, 1)
+    }
+
+    /// Records `n` occurances of a floating point value.
+    pub fn record_f64s(&self, val: f64, n: u64) -> Result<&Self, RecordError> {
+        let bin = match self.policy {
+            RecordPolicy::Lenient => Bin::from_f64(val),
+            RecordPolicy::Strict => Bin::try_from_f64(val)?,
+        };
+        self.add(bin, n)?;
+        Ok(self)
+    }
+
+    /// Returns a [`Histogram`] containing the values recorded so far.
+    ///
+    /// Values recorded concurrently with the snapshot may or may not be
+    /// included in it.
+    #[must_use]
+    pub fn snapshot(&self) -> Histogram {
+        self.collect(|counter| counter.load(Ordering::Relaxed))
+    }
+
+    /// Returns a [`Histogram`] containing the values recorded so far, and
+    /// resets this histogram.
+    ///
+    /// Every recorded value is included in exactly one snapshot, even if
+    /// values are recorded concurrently.
+    #[must_use]
+    pub fn snapshot_and_reset(&self) -> Histogram {
+        self.collect(|counter| counter.swap(0, Ordering::Relaxed))
+    }
+
+    fn add(&self, bin: Bin, n: u64) -> Result<(), RecordError> {
+        let counter = match Slot::for_bin(bin) {
+            Slot::Nan => &self.nan,
+            Slot::Zero => &self.zero,
+            Slot::Page { page, idx } => &self.page(page).0[idx],
+        };
+        match self.policy {
+            // counts saturate rather than wrapping, as they do in a
+            // `Histogram`. a `fetch_add` could be seen wrapped before the
+            // count was set back to `u64::MAX`, so this takes a
+            // compare-and-swap loop too.
+            RecordPolicy::Lenient => {
+                let _ = counter.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |count| {
+                    Some(count.saturating_add(n))
+                });
+                Ok(())
+            }
+            // only add to the count if it won't overflow.
+            RecordPolicy::Strict => counter
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |count| {
+                    count.checked_add(n)
+                })
+                .map(drop)
+                .map_err(|_| RecordError::CountSaturated { bin }),
+        }
+    }
+
+    /// Returns the page at `idx`, allocating it if it doesn't exist yet.
+    fn page(&self, idx: usize) -> &Page {
+        let slot = &self.pages[idx];
+        let page = slot.load(Ordering::Acquire);
+        if !page.is_null() {
+            // Safety: non-null pages are never freed until `self` is dropped.
+            return unsafe { &*page };
+        }
+
+        let new_page = Box::into_raw(Box::new(Page(core::array::from_fn(|_| AtomicU64::new(0)))));
+        match slot.compare_exchange(
+            ptr::null_mut(),
+            new_page,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        ) {
+            // Safety: we just stored this page, and it won't be freed until
+            // `self` is dropped.
+            Ok(_) => unsafe { &*new_page },
+            Err(page) => {
+                // another thread allocated the page first, so use theirs.
+                // Safety: `new_page` was never shared, so we still own it.
+                drop(unsafe { Box::from_raw(new_page) });
+                // Safety: non-null pages are never freed until `self` is
+                // dropped.
+                unsafe { &*page }
+            }
+        }
+    }
+
+    fn collect(&self, mut count: impl FnMut(&AtomicU64) -> u64) -> Histogram {
+        let mut bins = Vec::new();
+        let mut push = |bin, counter: &AtomicU64| {
+            let count = count(counter);
+            if count > 0 {
+                bins.push(Bucket { bin, count });
+            }
+        };
+
+        push(Bin::NAN, &self.nan);
+        push(Bin::ZERO, &self.zero);
+        for (page_idx, page) in self.pages.iter().enumerate() {
+            let page = page.load(Ordering::Acquire);
+            if page.is_null() {
+                continue;
+            }
+            // Safety: non-null pages are never freed until `self` is dropped.
+            let page = unsafe { &*page };
+            for (idx, counter) in page.0.iter().enumerate() {
+                push(Slot::bin(page_idx, idx), counter);
+            }
+        }
+
+        bins.sort_unstable_by_key(|bucket| bucket.bin);
+        Histogram {
+            bins,
+            policy: self.policy,
+        }
+    }
+}
+
+impl Default for AtomicHistogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for AtomicHistogram {
+    fn drop(&mut self) {
+        for page in self.pages.iter_mut() {
+            let page = *page.get_mut();
+            if !page.is_null() {
+                // Safety: we have exclusive access to `self`, so nobody else
+                // can be using the page.
+                drop(unsafe { Box::from_raw(page) });
+            }
+        }
+    }
+}
+
+impl fmt::Debug for AtomicHistogram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AtomicHistogram")
+            .field("bins", &self.snapshot().bins)
+            .finish()
+    }
+}
+
+// === impl Slot ===
+
+impl Slot {
+    fn for_bin(bin: Bin) -> Self {
+        if bin.is_nan() {
+            return Self::Nan;
+        }
+        match bin.parts() {
+            (0, _) => Self::Zero,
+            (val, exp) => Self::Page {
+                page: (val < 0) as usize * 256 + exp as u8 as usize,
+                idx: val.unsigned_abs() as usize - 10,
+            },
+        }
+    }
+
+    fn bin(page: usize, idx: usize) -> Bin {
+        let sign = if page >= 256 { -1 } else { 1 };
+        let val = sign * (idx as i8 + 10);
+        let exp = (page % 256) as u8 as i8;
+        Bin::from_parts(val, exp).expect("every page slot is a valid bin")
+    }
+}
@@ -1,10 +1,38 @@
-use core::{cmp, fmt, num};
+use crate::{DecodeError, RecordError};
+use core::{
+    cmp,
+    fmt::{self, Write},
+    num, str,
+};
 
 #[must_use = "a DisplayBin does nothing unless formatted"]
-pub struct DisplayBin<'hist>(pub(crate) &'hist Bucket);
+pub struct DisplayBin<'hist> {
+    bucket: &'hist Bucket,
+    canonical: bool,
+}
 
+/// A read-only view of one of a histogram's buckets: a bin and the number of
+/// values recorded in it.
+///
+/// Positive bins include their lower bound and exclude their upper bound
+/// (e.g. `[1.0, 1.1)`), while negative bins exclude their lower bound and
+/// include their upper bound (e.g. `(-1.1, -1.0]`). The zero bin contains only
+/// zero (and values too small to have a bin of their own), and the NaN bin
+/// contains NaN values and values too large to have a bin of their own.
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
-pub(crate) struct Bin {
+pub struct BucketRef<'hist>(pub(crate) &'hist Bucket);
+
+/// One of the bins that values are recorded in.
+///
+/// Every non-zero value is recorded in a bin identified by its first two
+/// significant digits and its base-10 exponent: for example, 1.234 is recorded
+/// in the bin `[1.2, 1.3)`. Zero has a bin of its own, and NaN values, and
+/// values too large in magnitude to have a bin, are recorded in the NaN bin.
+///
+/// Bins are ordered by their position on the real number line, except for the
+/// NaN bin, which comes first.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Bin {
     val: i8,
     exp: i8,
 }
@@ -15,16 +43,38 @@ pub(crate) struct Bucket {
     pub(crate) count: u64,
 }
 
-#[derive(Debug, Eq, PartialEq)]
-pub(crate) enum ParseBinError {
+/// The reason a bin string such as `H[1.2e-01]=2` could not be parsed.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ParseErrorKind {
+    /// The count after the `=` is not a valid integer.
     ParseCount(num::ParseIntError),
+    /// The value inside the `H[...]` is not a valid number.
     ParseBin(num::ParseFloatError),
+    /// There is nothing before the `=`.
     NoBin,
+    /// There is no `=` followed by a count.
     NoCount,
+    /// The bin string is malformed in some other way.
     Expected(&'static str),
+    /// The count is negative.
     NegCount,
 }
 
+/// A float literal formatted on the stack, from which a bin's exact bounds
+/// are parsed.
+#[derive(Default)]
+struct Literal {
+    buf: [u8; 16],
+    len: usize,
+}
+
+/// The number of bins with each exponent and sign.
+const PER_EXP: u16 = 90;
+
+/// The number of bins with each sign.
+const PER_SIGN: u16 = 256 * PER_EXP;
+
 /// Lookup table for f64 powers of 10.
 const POWS_OF_TEN: [f64; 256] = [
     1.0, 10.0, 100.0, 1000.0, 10000.0, 100000.0, 1e+06, 1e+07, 1e+08, 1e+09, 1e+10, 1e+11, 1e+12,
@@ -51,102 +101,405 @@ const POWS_OF_TEN: [f64; 256] = [
 
 // === impl DisplayBin ===
 
+impl<'hist> DisplayBin<'hist> {
+    pub(crate) fn new(bucket: &'hist Bucket) -> Self {
+        Self {
+            bucket,
+            canonical: false,
+        }
+    }
+
+    /// Returns a `DisplayBin` whose [`Display`](fmt::Display) implementation
+    /// formats the bin exactly as the Go library's `DecStrings` does, with a
+    /// signed, two-digit exponent (e.g. `H[1.2e-01]=2` rather than
+    /// `H[1.2e-1]=2`).
+    pub fn canonical(self) -> Self {
+        Self {
+            canonical: true,
+            ..self
+        }
+    }
+}
+
 impl fmt::Display for DisplayBin<'_> {
     #[inline]
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        fmt::Display::fmt(self.0, f)
+        if self.canonical {
+            self.bucket.fmt_canonical(f)
+        } else {
+            fmt::Display::fmt(self.bucket, f)
+        }
     }
 }
 
 impl fmt::LowerExp for DisplayBin<'_> {
     #[inline]
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        fmt::LowerExp::fmt(self.0, f)
+        fmt::LowerExp::fmt(self.bucket, f)
     }
 }
 
 impl fmt::UpperExp for DisplayBin<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        fmt::UpperExp::fmt(self.0, f)
+        fmt::UpperExp::fmt(self.bucket, f)
+    }
+}
+
+// === impl BucketRef ===
+
+impl<'hist> BucketRef<'hist> {
+    /// Returns this bucket's bin.
+    #[must_use]
+    pub fn bin(&self) -> Bin {
+        self.0.bin
+    }
+
+    /// Returns the lower bound of this bucket's bin, or NaN for the NaN bin, as
+    /// by [`Bin::lower_bound`].
+    #[must_use]
+    pub fn lower_bound(&self) -> f64 {
+        self.0.bin.lower_bound()
+    }
+
+    /// Returns the upper bound of this bucket's bin, or NaN for the NaN bin, as
+    /// by [`Bin::upper_bound`].
+    #[must_use]
+    pub fn upper_bound(&self) -> f64 {
+        self.0.bin.upper_bound()
+    }
+
+    /// Returns the midpoint of this bucket's bin, or NaN for the NaN bin.
+    #[must_use]
+    pub fn midpoint(&self) -> f64 {
+        self.0.bin.midpoint()
+    }
+
+    /// Returns the width of this bucket's bin, which is 0 for the zero bin and
+    /// NaN for the NaN bin.
+    #[must_use]
+    pub fn width(&self) -> f64 {
+        self.0.bin.bin_width()
+    }
+
+    /// Returns the number of values recorded in this bucket.
+    #[must_use]
+    pub fn count(&self) -> u64 {
+        self.0.count
+    }
+
+    /// Returns `true` if this is the NaN bin.
+    #[must_use]
+    pub fn is_nan(&self) -> bool {
+        self.0.bin.is_nan()
+    }
+
+    /// Returns a [`DisplayBin`] that formats this bucket.
+    pub fn display(&self) -> DisplayBin<'hist> {
+        DisplayBin::new(self.0)
+    }
+}
+
+// === impl Literal ===
+
+impl Literal {
+    fn as_str(&self) -> &str {
+        str::from_utf8(&self.buf[..self.len]).expect("only `str`s are written")
+    }
+}
+
+impl fmt::Write for Literal {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.len + s.len();
+        self.buf
+            .get_mut(self.len..end)
+            .ok_or(fmt::Error)?
+            .copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+// === impl ParseErrorKind ===
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ParseCount(e) => write!(f, "invalid bin count: {e}"),
+            Self::ParseBin(e) => write!(f, "invalid bin value: {e}"),
+            Self::NoBin => f.write_str("missing bin"),
+            Self::NoCount => f.write_str("missing bin count"),
+            Self::Expected(expected) => write!(f, "expected {expected}"),
+            Self::NegCount => f.write_str("bin count must not be negative"),
+        }
     }
 }
 
 // === impl Bin ===
 
 impl Bin {
-    pub(super) fn from_str(s: &str) -> Result<(Self, i64), ParseBinError> {
+    /// The bin that NaN and infinite values are recorded in.
+    pub const NAN: Self = Self { val: -1, exp: 0 };
+
+    /// The bin that zero is recorded in.
+    pub const ZERO: Self = Self { val: 0, exp: 0 };
+
+    /// The number of distinct bins, including the zero and NaN bins.
+    pub const COUNT: u16 = 2 + 2 * PER_SIGN;
+
+    /// Returns the bin that `val` would be recorded in.
+    #[must_use]
+    pub fn for_value(val: f64) -> Self {
+        Self::from_f64(val)
+    }
+
+    /// Returns the bin that the value `val * 10^scale` would be recorded in.
+    #[must_use]
+    pub fn for_int_scale(val: i64, scale: i32) -> Self {
+        Self::from_int_scale(val, scale)
+    }
+
+    /// Returns this bin's index in the list of all bins, sorted in the same
+    /// order as `Bin`'s [`Ord`] implementation.
+    ///
+    /// Indices are dense, starting with 0 for the NaN bin, and are less than
+    /// [`Bin::COUNT`]. They are stable, so they may be persisted.
+    #[must_use]
+    pub fn index(&self) -> u16 {
+        if self.is_nan() {
+            return 0;
+        }
+        // map exponents from -128..=127 to 0..=255.
+        let exp = (self.exp as u8 ^ 0x80) as u16;
+        match self.val {
+            0 => 1 + PER_SIGN,
+            // a larger exponent means a smaller negative value, and `val`
+            // counts up from -99.
+            val if val < 0 => 1 + (255 - exp) * PER_EXP + (val + 99) as u16,
+            val => 2 + PER_SIGN + exp * PER_EXP + (val - 10) as u16,
+        }
+    }
+
+    /// Returns the bin with the given [`index`](Bin::index), or `None` if
+    /// `index` is not less than [`Bin::COUNT`].
+    #[must_use]
+    pub fn from_index(index: u16) -> Option<Self> {
+        let (val, exp) = match index {
+            0 => return Some(Self::NAN),
+            i if i <= PER_SIGN => {
+                let i = i - 1;
+                ((i % PER_EXP) as i8 - 99, 255 - i / PER_EXP)
+            }
+            i if i == PER_SIGN + 1 => return Some(Self::ZERO),
+            i if i < Self::COUNT => {
+                let i = i - PER_SIGN - 2;
+                ((i % PER_EXP) as i8 + 10, i / PER_EXP)
+            }
+            _ => return None,
+        };
+        Some(Self {
+            val,
+            exp: (exp as u8 ^ 0x80) as i8,
+        })
+    }
+
+    /// Returns the bin immediately above this one, or `None` if this is the
+    /// largest bin or the NaN bin.
+    #[must_use]
+    pub fn next(&self) -> Option<Self> {
+        if self.is_nan() {
+            return None;
+        }
+        Self::from_index(self.index() + 1)
+    }
+
+    /// Returns the bin immediately below this one, or `None` if this is the
+    /// smallest bin or the NaN bin.
+    #[must_use]
+    pub fn prev(&self) -> Option<Self> {
+        match self.index() {
+            0 | 1 => None,
+            index => Self::from_index(index - 1),
+        }
+    }
+
+    /// Returns an iterator over every bin, in ascending order.
+    ///
+    /// The NaN bin comes first.
+    pub fn all() -> impl DoubleEndedIterator<Item = Self> + ExactSizeIterator {
+        (0..Self::COUNT).map(|index| Self::from_index(index).expect("every index is valid"))
+    }
+
+    /// Returns the lower bound of this bin, or NaN for the NaN bin.
+    ///
+    /// The bound is the `f64` nearest to its exact decimal value, so the lower
+    /// bound of `[1.2, 1.3)` is the same as the literal `1.2`.
+    #[must_use]
+    pub fn lower_bound(&self) -> f64 {
+        // the bin `val * 10^(exp - 1)` starts at its own value if it's positive
+        // or zero, and at `(val - 1) * 10^(exp - 1)` if it's negative.
+        match self.val {
+            0 => 0.0,
+            val if val < 0 => self.bound(i16::from(val) - 1),
+            val => self.bound(val.into()),
+        }
+    }
+
+    /// Returns the upper bound of this bin, or NaN for the NaN bin.
+    ///
+    /// The bound is the `f64` nearest to its exact decimal value, as written
+    /// for the bin's `le` boundary by [`Histogram::prometheus`].
+    ///
+    /// [`Histogram::prometheus`]: crate::Histogram::prometheus
+    #[must_use]
+    pub fn upper_bound(&self) -> f64 {
+        // the bin `val * 10^(exp - 1)` ends at `(val + 1) * 10^(exp - 1)` if
+        // it's positive, and at its own value if it's negative or zero.
+        match self.val {
+            0 => 0.0,
+            val if val < 0 => self.bound(val.into()),
+            val => self.bound(i16::from(val) + 1),
+        }
+    }
+
+    /// Returns the width of this bin, which is 0 for the zero bin and NaN for
+    /// the NaN bin.
+    #[must_use]
+    pub fn width(&self) -> f64 {
+        self.bin_width()
+    }
+
+    /// Parses a bin string, returning the bin and its count, or the reason it
+    /// is invalid and the byte offset in `s` at which the problem was found.
+    pub(super) fn from_str(s: &str) -> Result<(Self, i64), (ParseErrorKind, usize)> {
+        // every string we look at is a slice of `s`, so its offset in `s` is
+        // the distance between their pointers.
+        let at = |part: &str| part.as_ptr() as usize - s.as_ptr() as usize;
+
         // H[0.0e+00]=1
         let s = s.trim();
 
         let (bin_str, count_str) = {
             let mut parts = s.split('=');
-            let bin_str = parts.next().ok_or(ParseBinError::NoBin)?;
-            let count_str = parts.next().ok_or(ParseBinError::NoCount)?;
-            if parts.next().is_some() {
-                return Err(ParseBinError::Expected(
-                    "expected only one `=`, but found multiple",
+            let bin_str = parts.next().unwrap_or(s);
+            if bin_str.trim().is_empty() {
+                return Err((ParseErrorKind::NoBin, at(bin_str)));
+            }
+            let count_str = parts
+                .next()
+                .ok_or((ParseErrorKind::NoCount, at(s) + s.len()))?;
+            if let Some(extra) = parts.next() {
+                return Err((
+                    ParseErrorKind::Expected("only one `=`, but found multiple"),
+                    at(extra) - 1,
                 ));
             }
 
-            (bin_str, count_str)
+            (bin_str.trim(), count_str.trim())
         };
 
         // H[ <0.0 e+00> ]=1
-        let bin = bin_str
-            .trim()
-            .strip_prefix("H[")
-            .ok_or(ParseBinError::Expected("bin to start with `H[`"))?
+        let bin_str = bin_str.strip_prefix("H[").ok_or((
+            ParseErrorKind::Expected("bin to start with `H[`"),
+            at(bin_str),
+        ))?;
+        let bin_str = bin_str
             .strip_suffix(']')
-            .ok_or(ParseBinError::Expected("bin to end with `]`"))?
-            .trim()
+            .ok_or((
+                ParseErrorKind::Expected("bin to end with `]`"),
+                at(bin_str) + bin_str.len(),
+            ))?
+            .trim();
+        let bin = bin_str
             .parse::<f64>()
-            .map_err(ParseBinError::ParseBin)?;
+            .map_err(|e| (ParseErrorKind::ParseBin(e), at(bin_str)))?;
 
         let count = count_str
-            .trim()
             .parse::<i64>()
-            .map_err(ParseBinError::ParseCount)?;
+            .map_err(|e| (ParseErrorKind::ParseCount(e), at(count_str)))?;
         if count < 0 {
-            return Err(ParseBinError::NegCount);
+            return Err((ParseErrorKind::NegCount, at(count_str)));
         }
 
         Ok((Self::from_f64(bin), count))
     }
 
-    pub(crate) fn from_int_scale(mut val: i64, mut scale: i32) -> Self {
+    pub(crate) fn from_int_scale(val: i64, scale: i32) -> Self {
         if val == 0 {
-            return Self { val: 0, exp: 0 };
+            return Self::ZERO;
         }
-        let sign = val.signum();
-        val = val.abs();
-        if val < 10 {
-            val *= 10;
+        let mut abs = val.unsigned_abs();
+        let mut scale = i64::from(scale);
+        if abs < 10 {
+            abs *= 10;
         } else {
             scale += 1;
         }
 
-        if val >= 100 {
-            let log10 = val.checked_ilog10().unwrap_or(0);
-            val = val.checked_rem(10 * log10 as i64).unwrap_or(val);
-            scale += log10 as i32;
+        // drop all but the two most significant digits.
+        if abs >= 100 {
+            let dropped = abs.ilog10() - 1;
+            abs /= 10u64.pow(dropped);
+            scale += i64::from(dropped);
         }
 
         if scale > 127 {
-            val = 0xff;
-            scale = 0;
+            return Self::NAN;
         } else if scale < -128 {
-            val = 0;
-            scale = 0;
+            return Self::ZERO;
         }
 
-        val *= sign;
-
+        let abs = abs as i8;
         Self {
-            val: val as i8,
+            val: if val < 0 { -abs } else { abs },
             exp: scale as i8,
         }
     }
 
+    /// Returns the bin for `val * 10^scale`, or an error if it can't be
+    /// recorded exactly.
+    pub(crate) fn try_from_int_scale(val: i64, scale: i32) -> Result<Self, RecordError> {
+        let bin = Self::from_int_scale(val, scale);
+        let approx = || {
+            // `f64::powi` needs `std`. any `i64` scaled by more than 10^±400
+            // is infinite or zero anyway.
+            let scale = scale.clamp(-400, 400);
+            let pow = (0..scale.unsigned_abs()).fold(1.0, |pow, _| pow * 10.0);
+            if scale < 0 {
+                val as f64 / pow
+            } else {
+                val as f64 * pow
+            }
+        };
+        if bin.is_nan() {
+            Err(RecordError::Overflow(approx()))
+        } else if bin == Self::ZERO && val != 0 {
+            Err(RecordError::Underflow(approx()))
+        } else {
+            Ok(bin)
+        }
+    }
+
+    /// Returns the bin for `val`, or an error if it can't be recorded exactly.
+    pub(crate) fn try_from_f64(val: f64) -> Result<Self, RecordError> {
+        if !val.is_finite() {
+            return Err(RecordError::NotFinite(val));
+        }
+        let bin = Self::from_f64(val);
+        if bin.is_nan() {
+            Err(RecordError::Overflow(val))
+        } else if bin == Self::ZERO && val != 0.0 {
+            // values just below the largest power of ten round up into the
+            // zero bin, just like values that are too small.
+            if -1.0 < val && val < 1.0 {
+                Err(RecordError::Underflow(val))
+            } else {
+                Err(RecordError::Overflow(val))
+            }
+        } else {
+            Ok(bin)
+        }
+    }
+
     #[must_use]
     pub(crate) fn from_f64(mut f: f64) -> Self {
         let mut this = Self { val: 0, exp: 0 };
@@ -230,15 +583,16 @@ impl Bin {
         //     hb.val = -1
         //     hb.exp = 0
         // }
-        if !(this.val >= 10 && this.val < 100) || (this.val <= -10 && this.val > -100) {
+        if !((this.val >= 10 && this.val < 100) || (this.val <= -10 && this.val > -100)) {
             this.val = -1;
             this.exp = 0
         }
         this
     }
 
+    /// Returns the midpoint of this bin, or NaN for the NaN bin.
     #[must_use]
-    pub(crate) fn midpoint(&self) -> f64 {
+    pub fn midpoint(&self) -> f64 {
         // if hb.isNaN() {
         //     return math.NaN()
         // }
@@ -262,13 +616,14 @@ impl Bin {
     }
 
     // func (hb *bin) isNaN() bool {
+    /// Returns `true` if this is the NaN bin.
     #[must_use]
-    pub(crate) fn is_nan(&self) -> bool {
+    pub fn is_nan(&self) -> bool {
         // aval := hb.val
         // if aval < 0 {
         // 	aval = -aval
         // }
-        match self.val.abs() {
+        match self.val.unsigned_abs() {
             // if 99 < aval { // in [100... ]: nan
             // 	return true
             // }
@@ -346,9 +701,67 @@ impl Bin {
         self.pow_10() / 10.0
     }
 
+    /// Returns a bin from its raw value and exponent, or `None` if they do not
+    /// describe a valid bin.
+    #[must_use]
+    pub(crate) fn from_parts(val: i8, exp: i8) -> Option<Self> {
+        let bin = Self { val, exp };
+        bin.is_valid().then_some(bin)
+    }
+
+    /// Returns the bin this bin is collapsed into when compressing to the
+    /// minimum exponent `min_exp`: bins with a smaller exponent become the
+    /// zero bin, and bins with exactly that exponent keep only their first
+    /// digit.
+    #[must_use]
+    pub(crate) fn with_min_exp(self, min_exp: i8) -> Self {
+        if self.is_nan() || self.exp > min_exp {
+            return self;
+        }
+        if self.exp < min_exp {
+            return Self::ZERO;
+        }
+        Self {
+            val: self.val / 10 * 10,
+            exp: self.exp,
+        }
+    }
+
+    /// Returns this bin's raw value and exponent.
+    #[must_use]
+    pub(crate) fn parts(&self) -> (i8, i8) {
+        (self.val, self.exp)
+    }
+
+    /// Returns `true` if this is a bin that could have been produced by
+    /// recording a value: either a bin with a two-digit value, the zero bin,
+    /// or the NaN bin.
+    #[must_use]
+    pub(crate) fn is_valid(&self) -> bool {
+        match self.val.unsigned_abs() {
+            10..=99 => true,
+            0 => self.exp == 0,
+            _ => self.val == -1 && self.exp == 0,
+        }
+    }
+
     fn pow_10(&self) -> f64 {
         POWS_OF_TEN[self.exp as u8 as usize]
     }
+
+    /// Returns `digits * 10^(exp - 1)` rounded to the nearest `f64`, or NaN
+    /// for the NaN bin.
+    fn bound(&self, digits: i16) -> f64 {
+        if self.is_nan() {
+            return f64::NAN;
+        }
+        // multiplying by a power of ten can round, so parse the exact value
+        // instead.
+        let mut literal = Literal::default();
+        write!(literal, "{digits}e{}", i16::from(self.exp) - 1)
+            .expect("a bound always fits in a `Literal`");
+        literal.as_str().parse().expect("a valid float literal")
+    }
 }
 
 impl PartialOrd for Bin {
@@ -374,11 +787,16 @@ impl Ord for Bin {
         // `val` determines the sign, so if self's sign is different from
         // other's, it's always greater/less, regardless of the exponent.
         self.val.signum().cmp(&other.val.signum()).then_with(|| {
-            // if the two values have the same signs, compare the exponent...
-            self.exp
-                .cmp(&other.exp)
-                // and if they have the same exponent, finally compare the value
-                .then_with(|| self.val.cmp(&other.val))
+            // if the two values have the same signs, compare the exponent. a
+            // larger exponent means a larger magnitude, so for negative
+            // values, it means a *smaller* value...
+            let exp = if self.val < 0 {
+                other.exp.cmp(&self.exp)
+            } else {
+                self.exp.cmp(&other.exp)
+            };
+            // and if they have the same exponent, finally compare the value
+            exp.then_with(|| self.val.cmp(&other.val))
         })
     }
 }
@@ -386,6 +804,10 @@ impl Ord for Bin {
 // === impl Bucket ===
 
 impl Bucket {
+    /// The maximum length of a serialized bucket: the bin's value and
+    /// exponent, the width of the count, and up to eight bytes of count.
+    pub(crate) const MAX_SERIALIZED_LEN: usize = 11;
+
     pub(crate) fn count(&self) -> Option<u64> {
         if self.bin.is_nan() {
             return None;
@@ -393,6 +815,27 @@ impl Bucket {
 
         Some(self.count)
     }
+
+    /// Formats this bucket the way the Go library's `DecStrings` does, with a
+    /// signed, two-digit exponent (e.g. `H[1.2e-01]=2`).
+    pub(crate) fn fmt_canonical(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // fmt.Sprintf("H[%3.1e]=%d", bin.value(), bin.count)
+        let Bin { val, exp } = self.bin;
+        if self.bin.is_nan() {
+            return write!(f, "H[NaN]={}", self.count);
+        }
+        let sign = if val < 0 { "-" } else { "" };
+        let val = val.unsigned_abs();
+        let exp_sign = if exp < 0 { '-' } else { '+' };
+        write!(
+            f,
+            "H[{sign}{}.{}e{exp_sign}{:02}]={}",
+            val / 10,
+            val % 10,
+            exp.unsigned_abs(),
+            self.count
+        )
+    }
     // func (hb *bin) setFromFloat64(d float64) *bin { //nolint:unparam
 
     // func (h *Histogram) updateOldBinAt(idx uint16, count int64) uint64 {
@@ -411,6 +854,84 @@ impl Bucket {
         self.count = self.count.saturating_add_signed(count)
     }
 
+    // func writeBin(out io.Writer, in bin) (err error) {
+    /// Serializes this bucket into `buf`, returning the serialized bytes.
+    pub(crate) fn serialize<'buf>(
+        &self,
+        buf: &'buf mut [u8; Self::MAX_SERIALIZED_LEN],
+    ) -> &'buf [u8] {
+        // err = binary.Write(out, binary.BigEndian, in.val)
+        // err = binary.Write(out, binary.BigEndian, in.exp)
+        buf[0] = self.bin.val as u8;
+        buf[1] = self.bin.exp as u8;
+        // var tgtType int8 = 7
+        // for i := int8(0); i < tgtType; i++ {
+        //     if in.count <= bvlLimits[i] {
+        //         tgtType = i
+        //         break
+        //     }
+        // }
+        // err = binary.Write(out, binary.BigEndian, uint8(tgtType))
+        let width = 7 - (self.count.leading_zeros() as usize / 8).min(7);
+        buf[2] = width as u8;
+        // for i := tgtType; i >= 0; i-- {
+        //     v := uint8(in.count >> (uint8(i) * 8) & 0xff)
+        //     err = binary.Write(out, binary.BigEndian, v)
+        // }
+        let count = self.count.to_be_bytes();
+        buf[3..4 + width].copy_from_slice(&count[7 - width..]);
+        &buf[..4 + width]
+    }
+
+    // func readBin(in io.Reader) (out bin, err error) {
+    /// Deserializes a single bucket from the start of `bytes`, returning the
+    /// bucket and the number of bytes it occupied.
+    ///
+    /// `offset` is the position of `bytes` within the complete input, and is
+    /// used only for error reporting.
+    pub(crate) fn deserialize(bytes: &[u8], offset: usize) -> Result<(Self, usize), DecodeError> {
+        // err = binary.Read(in, binary.BigEndian, &out.val)
+        // err = binary.Read(in, binary.BigEndian, &out.exp)
+        // var bvl uint8
+        // err = binary.Read(in, binary.BigEndian, &bvl)
+        let &[val, exp, width, ref rest @ ..] = bytes else {
+            return Err(DecodeError::Truncated {
+                offset: offset + bytes.len(),
+                needed: 3 - bytes.len(),
+            });
+        };
+        // if bvl > uint8(BVL8) {
+        //     return out, fmt.Errorf("encoding error: bvl value is greater than max allowable")
+        // }
+        if width > 7 {
+            return Err(DecodeError::InvalidCountWidth {
+                offset: offset + 2,
+                width,
+            });
+        }
+        let (val, exp) = (val as i8, exp as i8);
+        let bin = Bin::from_parts(val, exp).ok_or(DecodeError::InvalidBin { offset, val, exp })?;
+
+        // bcount := make([]uint8, 8)
+        // n, err := in.Read(bcount[0 : bvl+1])
+        let len = width as usize + 1;
+        let Some(count_bytes) = rest.get(..len) else {
+            return Err(DecodeError::Truncated {
+                offset: offset + 3 + rest.len(),
+                needed: len - rest.len(),
+            });
+        };
+        // out.count = 0
+        // for i := 0; i < int(bvl+1); i++ {
+        //     out.count |= uint64(bcount[i]) << (uint8(int(bvl)-i) * 8)
+        // }
+        let count = count_bytes
+            .iter()
+            .fold(0u64, |count, &byte| (count << 8) | byte as u64);
+
+        Ok((Self { bin, count }, 3 + len))
+    }
+
     // // func (hb *bin) compare(h2 *bin) int {
     // pub(crate) fn difference(&self, other: &Self) -> isize {
     // //     var v1, v2 int
@@ -463,14 +984,113 @@ impl fmt::UpperExp for Bucket {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use proptest::{prop_assert_eq, proptest};
+    use proptest::{prelude::*, prop_assert, prop_assert_eq, prop_assume, proptest};
 
     proptest! {
         #[test]
         fn from_int_scale_matches_go(val: i64, scale: i32) {
-            prop_assert_eq!(Bin::from_int_scale(val, scale), from_int_scale_go(val, scale))
+            prop_assert_eq!(Bin::from_int_scale(val, scale), go_nan(from_int_scale_go(val, scale)))
+        }
+
+        #[test]
+        fn from_int_scale_matches_go_in_range(val: i64, scale in -150i32..150) {
+            // most `i32` scales are out of range, so check the scales that
+            // usually give a bin too.
+            prop_assert_eq!(Bin::from_int_scale(val, scale), go_nan(from_int_scale_go(val, scale)))
+        }
+
+        #[test]
+        fn from_int_scale_matches_from_f64(val in -1_000_000_000i64..1_000_000_000, scale in -100i32..100) {
+            let f = format!("{val}e{scale}").parse::<f64>().unwrap();
+            prop_assert_eq!(Bin::from_int_scale(val, scale), Bin::from_f64(f), "{}", f)
+        }
+
+        #[test]
+        fn index_matches_ord(a in valid_bin(), b in valid_bin()) {
+            prop_assert_eq!(a.index().cmp(&b.index()), a.cmp(&b), "{:?} vs {:?}", a, b)
+        }
+
+        #[test]
+        fn from_index_roundtrips(bin in valid_bin()) {
+            prop_assert_eq!(Bin::from_index(bin.index()), Some(bin))
+        }
+
+        #[test]
+        fn ord_matches_real_axis(a in valid_bin(), b in valid_bin()) {
+            prop_assume!(!a.is_nan() && !b.is_nan());
+            // bins are disjoint intervals, so comparing their lower bounds
+            // compares the bins.
+            prop_assert_eq!(
+                a.left().partial_cmp(&b.left()),
+                Some(a.cmp(&b)),
+                "{:?} ({}) vs {:?} ({})", a, a.left(), b, b.left()
+            )
         }
 
+        #[test]
+        fn from_f64_contains_value(f in proptest::num::f64::NORMAL) {
+            let bin = Bin::from_f64(f);
+            prop_assume!(bin != Bin::ZERO && !bin.is_nan());
+            let (left, right) = (bin.left(), bin.left() + bin.bin_width());
+            // allow for rounding error at the edges of the bin.
+            let slop = bin.bin_width() * 1e-9;
+            prop_assert!(
+                left - slop <= f && f <= right + slop,
+                "{} not in {:?} [{}, {}]", f, bin, left, right
+            );
+        }
+    }
+
+    #[test]
+    fn all_bins() {
+        let bins = Bin::all().collect::<Vec<_>>();
+        assert_eq!(bins.len(), Bin::COUNT as usize);
+        assert_eq!(bins[0], Bin::NAN);
+        for (index, bin) in bins.iter().enumerate() {
+            assert!(bin.is_valid(), "{bin:?}");
+            assert_eq!(bin.index() as usize, index, "{bin:?}");
+        }
+        for pair in bins[1..].windows(2) {
+            assert!(pair[0] < pair[1], "{:?} < {:?}", pair[0], pair[1]);
+            assert_eq!(pair[0].next(), Some(pair[1]));
+            assert_eq!(pair[1].prev(), Some(pair[0]));
+        }
+        assert_eq!(Bin::from_index(Bin::COUNT), None);
+        assert_eq!(Bin::NAN.next(), None);
+        assert_eq!(Bin::NAN.prev(), None);
+        assert_eq!(bins[1].prev(), None);
+        assert_eq!(bins[bins.len() - 1].next(), None);
+    }
+
+    #[test]
+    fn bounds_match_literals() {
+        for bin in Bin::all().filter(|bin| !bin.is_nan() && *bin != Bin::ZERO) {
+            let (val, exp) = bin.parts();
+            let exp = i16::from(exp) - 1;
+            let (lower, upper) = if val > 0 {
+                (i16::from(val), i16::from(val) + 1)
+            } else {
+                (i16::from(val) - 1, val.into())
+            };
+            let literal = |digits: i16| format!("{digits}e{exp}").parse::<f64>().unwrap();
+            assert_eq!(bin.lower_bound(), literal(lower), "{bin:?}");
+            assert_eq!(bin.upper_bound(), literal(upper), "{bin:?}");
+        }
+        assert_eq!(Bin::ZERO.lower_bound(), 0.0);
+        assert_eq!(Bin::ZERO.upper_bound(), 0.0);
+        assert!(Bin::NAN.lower_bound().is_nan());
+        assert!(Bin::NAN.upper_bound().is_nan());
+    }
+
+    fn valid_bin() -> impl Strategy<Value = Bin> {
+        prop_oneof![
+            Just(Bin::NAN),
+            Just(Bin::ZERO),
+            (10i8..100, any::<i8>(), any::<bool>()).prop_map(|(val, exp, neg)| Bin {
+                val: if neg { -val } else { val },
+                exp,
+            }),
+        ]
     }
 
     fn from_int_scale_go(mut val: i64, mut scale: i32) -> Bin {
@@ -530,4 +1150,67 @@ mod tests {
             exp: scale as i8,
         }
     }
+
+    #[test]
+    fn from_f64_negative() {
+        // negative values used to be recorded in the NaN bin, as the check
+        // that `val` has two digits only applied to positive values.
+        for f in [-1.0, -1.5, -0.012, -9.9e100] {
+            let bin = Bin::from_f64(f);
+            assert!(!bin.is_nan(), "{f}: {bin:?}");
+            assert!((bin.value() - f).abs() <= f.abs() * 1e-9, "{f}: {bin:?}");
+        }
+        assert!(Bin::from_f64(-1e200).is_nan());
+    }
+
+    #[test]
+    fn ord_negative() {
+        // a larger exponent means a smaller negative value. bins with the same
+        // sign used to be ordered by exponent first whatever their sign, so
+        // -100 sorted after -1.
+        let bins = [-100.0, -10.0, -1.5, -1.0, -0.1, 0.0, 0.1, 1.0, 10.0].map(Bin::from_f64);
+        for pair in bins.windows(2) {
+            assert!(pair[0] < pair[1], "{:?} < {:?}", pair[0], pair[1]);
+            assert!(
+                pair[0].index() < pair[1].index(),
+                "{:?} < {:?}",
+                pair[0],
+                pair[1]
+            );
+        }
+    }
+
+    #[test]
+    fn from_int_scale_digits() {
+        // values of 100 or more used to keep the remainder of dividing by
+        // `10 * log10(val)` rather than their two most significant digits, so
+        // 1234 was recorded in the bin for 4e3.
+        assert_eq!(Bin::from_int_scale(1234, 0), Bin { val: 12, exp: 3 });
+        assert_eq!(Bin::from_int_scale(-98_765, -3), Bin { val: -98, exp: 1 });
+        assert_eq!(Bin::from_int_scale(1234, 0), Bin::from_f64(1234.0));
+        // negating `i64::MIN` used to overflow.
+        assert_eq!(
+            Bin::from_int_scale(i64::MIN, 0),
+            Bin::from_f64(i64::MIN as f64)
+        );
+    }
+
+    #[test]
+    fn from_int_scale_overflow() {
+        assert_eq!(Bin::from_int_scale(1, 200), Bin::NAN);
+        // like the Go implementation, this used to return an invalid bin,
+        // `{ val: 1, exp: 0 }`, that compared unequal to the NaN bin.
+        assert_eq!(Bin::from_int_scale(-1, 200), Bin::NAN);
+        assert_eq!(Bin::from_int_scale(-1, -200), Bin::ZERO);
+    }
+
+    /// Returns the NaN bin if `bin` is NaN, since the Go implementation
+    /// returns an invalid bin for negative values too large to have a bin.
+    fn go_nan(bin: Bin) -> Bin {
+        if bin.is_nan() {
+            Bin::NAN
+        } else {
+            bin
+        }
+    }
 }
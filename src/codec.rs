@@ -0,0 +1,250 @@
+//! The binary histogram encoding shared with `libcircllhist` and the Go
+//! `circonusllhist` package.
+//!
+//! A serialized histogram is a big-endian `u16` count of buckets, followed by
+//! that many buckets. Each bucket is encoded as the bin's value and exponent
+//! (one byte each), one byte holding the number of bytes used to encode the
+//! count minus one, and then the count itself as a big-endian integer of that
+//! many bytes.
+//!
+//! IRONdb and Circonus HTTPTrap carry the same encoding wrapped in standard,
+//! padded base64 (the "b64" encoding).
+use crate::{bin::Bucket, Histogram};
+use alloc::{string::String, vec::Vec};
+use core::fmt::{self, Write};
+
+/// An error returned when deserializing a histogram from its binary encoding.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum DecodeError {
+    /// The input ended `needed` bytes before the end of the histogram.
+    Truncated { offset: usize, needed: usize },
+    /// A bucket's count was encoded with more than eight bytes.
+    InvalidCountWidth { offset: usize, width: u8 },
+    /// A bucket's value and exponent do not describe a valid bin.
+    InvalidBin { offset: usize, val: i8, exp: i8 },
+    /// The input continued past the end of the histogram.
+    TrailingBytes { offset: usize },
+    /// The byte at `offset` in a base64-encoded histogram is not valid
+    /// base64.
+    InvalidBase64 { offset: usize },
+}
+
+/// The standard base64 alphabet.
+const B64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+impl Histogram {
+    /// Writes the binary encoding of this histogram to `writer`.
+    ///
+    /// The output is byte-for-byte identical to `libcircllhist`'s
+    /// `hist_serialize` and the Go library's `Histogram.Serialize`.
+    #[cfg(feature = "std")]
+    pub fn serialize_into(&self, writer: &mut impl std::io::Write) -> std::io::Result<()> {
+        let mut buf = [0; Bucket::MAX_SERIALIZED_LEN];
+        writer.write_all(&self.serialized_bins().to_be_bytes())?;
+        for bucket in self.bins.iter().filter(|bucket| bucket.count > 0) {
+            writer.write_all(bucket.serialize(&mut buf))?;
+        }
+        Ok(())
+    }
+
+    /// Returns the binary encoding of this histogram.
+    ///
+    /// The output is byte-for-byte identical to `libcircllhist`'s
+    /// `hist_serialize` and the Go library's `Histogram.Serialize`.
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = [0; Bucket::MAX_SERIALIZED_LEN];
+        let mut bytes = Vec::with_capacity(2 + self.bins.len() * 4);
+        bytes.extend_from_slice(&self.serialized_bins().to_be_bytes());
+        for bucket in self.bins.iter().filter(|bucket| bucket.count > 0) {
+            bytes.extend_from_slice(bucket.serialize(&mut buf));
+        }
+        bytes
+    }
+
+    // func DeserializeWithOptions(in io.Reader, options ...Option) (h *Histogram, err error) {
+    /// Reads a histogram from its binary encoding, as produced by
+    /// [`Histogram::to_bytes`], `libcircllhist`'s `hist_serialize`, or the Go
+    /// library's `Histogram.Serialize`.
+    ///
+    /// Buckets for the same bin are coalesced, and buckets need not be sorted.
+    /// The entire input must be consumed by the histogram.
+    pub fn deserialize(bytes: &[u8]) -> Result<Self, DecodeError> {
+        // var nbin int16
+        // err = binary.Read(in, binary.BigEndian, &nbin)
+        let (nbins, mut rest) = match bytes {
+            &[hi, lo, ref rest @ ..] => (u16::from_be_bytes([hi, lo]), rest),
+            _ => {
+                return Err(DecodeError::Truncated {
+                    offset: bytes.len(),
+                    needed: 2 - bytes.len(),
+                })
+            }
+        };
+        let mut offset = 2;
+
+        // h = New(options...)
+        // for ii := int16(0); ii < nbin; ii++ {
+        //     bb, err := readBin(in)
+        //     if err != nil {
+        //         return h, err
+        //     }
+        //     h.insertBin(&bb.bucket, int64(bb.count))
+        // }
+        let mut histogram = Self::with_capacity(nbins as usize);
+        for _ in 0..nbins {
+            let (bucket, len) = Bucket::deserialize(rest, offset)?;
+            histogram.insert_bucket(bucket);
+            rest = &rest[len..];
+            offset += len;
+        }
+
+        if !rest.is_empty() {
+            return Err(DecodeError::TrailingBytes { offset });
+        }
+
+        Ok(histogram)
+    }
+
+    // func (h *Histogram) SerializeB64() (string, error) {
+    /// Returns the base64-encoded binary encoding of this histogram, as stored
+    /// by IRONdb.
+    ///
+    /// This is equivalent to the Go library's `Histogram.SerializeB64` and
+    /// `libcircllhist`'s `hist_serialize_b64`.
+    #[must_use]
+    pub fn to_b64(&self) -> String {
+        let mut b64 = String::with_capacity(b64_len(2 + self.bins.len() * 4));
+        self.write_b64(&mut b64)
+            .expect("writing to a `String` never fails");
+        b64
+    }
+
+    /// Writes the base64-encoded binary encoding of this histogram to
+    /// `writer`.
+    ///
+    /// See [`Histogram::to_b64`] for details.
+    pub fn write_b64(&self, writer: &mut impl Write) -> fmt::Result {
+        b64_encode(&self.to_bytes(), writer)
+    }
+
+    // func DeserializeB64(input string) (*Histogram, error) {
+    /// Reads a histogram from its base64-encoded binary encoding, as produced
+    /// by [`Histogram::to_b64`].
+    ///
+    /// The trailing `=` padding may be omitted. Offsets in
+    /// [`DecodeError::InvalidBase64`] refer to positions in `b64`, while all
+    /// other errors refer to positions in the decoded bytes.
+    pub fn from_b64(b64: &str) -> Result<Self, DecodeError> {
+        Self::from_b64_bytes(b64.as_bytes())
+    }
+
+    /// Reads a histogram from base64-encoded bytes.
+    ///
+    /// See [`Histogram::from_b64`] for details.
+    pub fn from_b64_bytes(b64: &[u8]) -> Result<Self, DecodeError> {
+        // data, err := base64.StdEncoding.DecodeString(input)
+        // if err != nil {
+        //     return nil, err
+        // }
+        // return Deserialize(bytes.NewBuffer(data))
+        Self::deserialize(&b64_decode(b64)?)
+    }
+
+    /// Returns the number of buckets that will be serialized. Empty buckets
+    /// are skipped, as they are by the reference implementations.
+    fn serialized_bins(&self) -> u16 {
+        let nbins = self.bins.iter().filter(|bucket| bucket.count > 0).count();
+        // there are fewer than `u16::MAX` distinct bins, so this can't truncate.
+        debug_assert!(nbins <= u16::MAX as usize);
+        nbins as u16
+    }
+}
+
+/// Returns the length of the padded base64 encoding of `len` bytes.
+fn b64_len(len: usize) -> usize {
+    (len + 2) / 3 * 4
+}
+
+fn b64_encode(bytes: &[u8], writer: &mut impl Write) -> fmt::Result {
+    for chunk in bytes.chunks(3) {
+        let mut group = [0; 3];
+        group[..chunk.len()].copy_from_slice(chunk);
+        let group = u32::from_be_bytes([0, group[0], group[1], group[2]]);
+        // a chunk of `n` bytes is encoded as `n + 1` characters, padded to 4.
+        for i in 0..4 {
+            if i <= chunk.len() {
+                let sextet = (group >> (18 - 6 * i)) & 0x3f;
+                writer.write_char(B64_ALPHABET[sextet as usize] as char)?;
+            } else {
+                writer.write_char('=')?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn b64_decode(b64: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    // padding is optional, but if it's present, there may be at most two
+    // `=`s, and they must complete the final group of four characters.
+    let unpadded = match b64 {
+        [rest @ .., b'=', b'='] | [rest @ .., b'='] if b64.len() % 4 == 0 => rest,
+        _ => b64,
+    };
+    if unpadded.len() % 4 == 1 {
+        return Err(DecodeError::InvalidBase64 {
+            offset: unpadded.len() - 1,
+        });
+    }
+
+    let mut bytes = Vec::with_capacity(unpadded.len() / 4 * 3 + 2);
+    for (i, chunk) in unpadded.chunks(4).enumerate() {
+        let mut group = 0u32;
+        for (j, &c) in chunk.iter().enumerate() {
+            let sextet = match c {
+                b'A'..=b'Z' => c - b'A',
+                b'a'..=b'z' => c - b'a' + 26,
+                b'0'..=b'9' => c - b'0' + 52,
+                b'+' => 62,
+                b'/' => 63,
+                _ => return Err(DecodeError::InvalidBase64 { offset: i * 4 + j }),
+            };
+            group |= (sextet as u32) << (18 - 6 * j);
+        }
+        // a group of `n` characters encodes `n - 1` bytes.
+        bytes.extend_from_slice(&group.to_be_bytes()[1..chunk.len()]);
+    }
+    Ok(bytes)
+}
+
+// === impl DecodeError ===
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Truncated { offset, needed } => write!(
+                f,
+                "histogram truncated at byte {offset}: expected {needed} more bytes"
+            ),
+            Self::InvalidCountWidth { offset, width } => write!(
+                f,
+                "invalid bucket count width {} at byte {offset} (must be at most 8)",
+                *width as usize + 1
+            ),
+            Self::InvalidBin { offset, val, exp } => {
+                write!(f, "invalid bin (val: {val}, exp: {exp}) at byte {offset}")
+            }
+            Self::TrailingBytes { offset } => {
+                write!(
+                    f,
+                    "unexpected trailing bytes after histogram at byte {offset}"
+                )
+            }
+            Self::InvalidBase64 { offset } => write!(f, "invalid base64 at byte {offset}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DecodeError {}
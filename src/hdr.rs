@@ -0,0 +1,137 @@
+//! Conversion to and from [`hdrhistogram::Histogram`]s.
+//!
+//! HdrHistogram records unsigned integers, keeping a configurable number of
+//! significant decimal digits, usually 3. A bin keeps only 2 significant
+//! digits, so converting an HdrHistogram into a histogram loses precision:
+//! each value lands in a bin up to 10% wide, relative to its values. Values
+//! below 100 are recorded exactly, as are any values HdrHistogram tracks with
+//! no more than 2 significant digits.
+//!
+//! Converting the other way, every value in a bin is recorded at the bin's
+//! midpoint, which is within 5% of each of the values, and rounded to an
+//! integer. HdrHistogram has no negative or NaN values, and rounding loses
+//! the bins of small values entirely unless the values are scaled up first,
+//! so conversions take the power of ten an HdrHistogram value is in units of:
+//! with a scale of -6, an HdrHistogram value of 1 is a histogram value of
+//! `1e-6`, like recording microseconds as seconds.
+use crate::{Bin, Histogram};
+use core::fmt;
+use hdrhistogram::Counter;
+
+/// The number of significant digits HdrHistogram keeps in histograms
+/// converted from a [`Histogram`], which is enough to keep every bin's
+/// midpoint distinct.
+const SIGFIG: u8 = 3;
+
+/// An error returned when converting a [`Histogram`] into an
+/// [`hdrhistogram::Histogram`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum HdrHistogramError {
+    /// The histogram has values in the NaN bin.
+    NotANumber,
+    /// The histogram has values in the negative bin `bin`.
+    Negative { bin: Bin },
+    /// The midpoint of the bin `bin`, scaled, is too large for an
+    /// HdrHistogram value.
+    Overflow { bin: Bin },
+}
+
+impl Histogram {
+    /// Converts an HdrHistogram into a histogram, where an HdrHistogram value
+    /// of `v` is the value `v * 10^scale`.
+    ///
+    /// Each value HdrHistogram reports is recorded with
+    /// [`Histogram::record_int_scales`], at the median of the values it can't
+    /// distinguish from it. Values larger than `i64::MAX` are recorded as
+    /// `i64::MAX`.
+    #[must_use]
+    pub fn from_hdrhistogram<T: Counter>(hdr: &hdrhistogram::Histogram<T>, scale: i32) -> Self {
+        let mut histogram = Histogram::new();
+        for value in hdr.iter_recorded() {
+            let val = hdr.median_equivalent(value.value_iterated_to());
+            let val = i64::try_from(val).unwrap_or(i64::MAX);
+            let mut count = value.count_at_value().as_u64();
+            while count > 0 {
+                let n = count.min(i64::MAX as u64);
+                histogram
+                    .record_int_scales(val, scale, n as i64)
+                    .expect("lenient histograms record every value");
+                count -= n;
+            }
+        }
+        histogram
+    }
+
+    /// Converts this histogram into an auto-resizing HdrHistogram with 3
+    /// significant digits, where an HdrHistogram value of `v` is the value
+    /// `v * 10^scale`.
+    ///
+    /// Each bin's values are recorded at its midpoint, scaled and rounded to
+    /// the nearest integer. The zero bin's values are recorded as 0.
+    pub fn to_hdrhistogram(
+        &self,
+        scale: i32,
+    ) -> Result<hdrhistogram::Histogram<u64>, HdrHistogramError> {
+        let mut hdr = hdrhistogram::Histogram::new(SIGFIG).expect("3 significant digits are valid");
+        let factor = 10f64.powi(-scale);
+        for bucket in &self.bins {
+            let bin = bucket.bin;
+            if bin.is_nan() {
+                return Err(HdrHistogramError::NotANumber);
+            }
+            if bin < Bin::ZERO {
+                return Err(HdrHistogramError::Negative { bin });
+            }
+            let val = (bin.midpoint() * factor).round();
+            // `u64::MAX as f64` rounds up to 2^64, which is out of range.
+            if val >= u64::MAX as f64 {
+                return Err(HdrHistogramError::Overflow { bin });
+            }
+            hdr.record_n(val as u64, bucket.count)
+                .map_err(|_| HdrHistogramError::Overflow { bin })?;
+        }
+        Ok(hdr)
+    }
+}
+
+impl<T: Counter> From<&hdrhistogram::Histogram<T>> for Histogram {
+    /// Converts an HdrHistogram into a histogram with the same values, as by
+    /// [`Histogram::from_hdrhistogram`] with a scale of 0.
+    fn from(hdr: &hdrhistogram::Histogram<T>) -> Self {
+        Histogram::from_hdrhistogram(hdr, 0)
+    }
+}
+
+impl TryFrom<&Histogram> for hdrhistogram::Histogram<u64> {
+    type Error = HdrHistogramError;
+
+    /// Converts a histogram into an HdrHistogram with the same values, as by
+    /// [`Histogram::to_hdrhistogram`] with a scale of 0.
+    fn try_from(histogram: &Histogram) -> Result<Self, Self::Error> {
+        histogram.to_hdrhistogram(0)
+    }
+}
+
+// === impl HdrHistogramError ===
+
+impl fmt::Display for HdrHistogramError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotANumber => f.write_str("HdrHistogram can't record NaN values"),
+            Self::Negative { bin } => write!(
+                f,
+                "HdrHistogram can't record negative values (bin {:e})",
+                bin.value()
+            ),
+            Self::Overflow { bin } => write!(
+                f,
+                "bin {:e} is too large to record in an HdrHistogram",
+                bin.value()
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for HdrHistogramError {}
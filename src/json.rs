@@ -0,0 +1,398 @@
+//! The JSON histogram representation used by IRONdb and Circonus.
+//!
+//! A histogram document is an object whose `_type` is `"h"` and whose
+//! `_value` is an array of bin strings, such as
+//! `{"_type":"h","_value":["H[1.2e-01]=2"]}`. This is the shape accepted by
+//! HTTPTrap and returned by IRONdb, whose fetch APIs also return bare arrays of
+//! bin strings.
+//!
+//! Only this small subset of JSON is needed, so rather than depending on a
+//! JSON library, it is encoded and parsed by hand here.
+use crate::{Histogram, ParseError};
+use alloc::{borrow::Cow, string::String};
+use core::fmt::{self, Write};
+
+/// An error returned when parsing a histogram from an IRONdb or Circonus JSON
+/// document.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum JsonError {
+    /// The input is not valid JSON, or not a histogram document; `expected`
+    /// describes what was expected at byte `offset`.
+    Syntax {
+        offset: usize,
+        expected: &'static str,
+    },
+    /// The document's `_type` is not `"h"`.
+    NotHistogram { offset: usize, found: String },
+    /// The document has no `_type`.
+    MissingType,
+    /// The document has no `_value`.
+    MissingValue,
+    /// A bin string in `_value` is invalid.
+    ///
+    /// The error's offset is relative to the start of the JSON input, and
+    /// assumes the bin string contains no escape sequences.
+    Bin(ParseError),
+}
+
+/// How deeply nested values may be in the fields of a histogram document
+/// that are skipped.
+const MAX_DEPTH: usize = 64;
+
+impl Histogram {
+    /// Returns this histogram as an IRONdb and Circonus HTTPTrap JSON
+    /// document, such as `{"_type":"h","_value":["H[1.2e-01]=2"]}`.
+    ///
+    /// The bin strings are formatted as by [`Histogram::dec_strings`].
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        let mut json = String::with_capacity(24 + self.bins.len() * 20);
+        self.write_json(&mut json)
+            .expect("writing to a `String` never fails");
+        json
+    }
+
+    /// Writes this histogram to `writer` as an IRONdb and Circonus HTTPTrap
+    /// JSON document.
+    ///
+    /// See [`Histogram::to_json`] for details.
+    pub fn write_json(&self, writer: &mut impl Write) -> fmt::Result {
+        writer.write_str(r#"{"_type":"h","_value":["#)?;
+        for (i, bin) in self.display_bins().enumerate() {
+            if i > 0 {
+                writer.write_char(',')?;
+            }
+            // bin strings never contain characters that need escaping.
+            write!(writer, "\"{}\"", bin.canonical())?;
+        }
+        writer.write_str("]}")
+    }
+
+    /// Parses a histogram from an IRONdb or Circonus JSON document, such as
+    /// `{"_type":"h","_value":["H[1.2e-01]=2"]}`, or from a bare array of bin
+    /// strings, such as `["H[1.2e-01]=2"]`.
+    ///
+    /// Fields of the document other than `_type` and `_value` are ignored. Bin
+    /// strings for the same bin are coalesced, as by [`Histogram::from_strs`].
+    pub fn from_json(json: &str) -> Result<Self, JsonError> {
+        let mut parser = Parser { json, pos: 0 };
+        parser.skip_whitespace();
+        let histogram = match parser.peek() {
+            Some(b'[') => parser.bins()?,
+            Some(b'{') => parser.document()?,
+            _ => return Err(parser.error("an object or array")),
+        };
+        parser.skip_whitespace();
+        if parser.pos < json.len() {
+            return Err(parser.error("end of input"));
+        }
+        Ok(histogram)
+    }
+}
+
+struct Parser<'json> {
+    json: &'json str,
+    pos: usize,
+}
+
+impl<'json> Parser<'json> {
+    /// Parses a histogram document, starting at its opening `{`.
+    fn document(&mut self) -> Result<Histogram, JsonError> {
+        self.expect(b'{', "`{`")?;
+        let mut histogram = None;
+        let mut is_histogram = false;
+        self.skip_whitespace();
+        if !self.eat(b'}') {
+            loop {
+                self.skip_whitespace();
+                let (_, key) = self.string()?;
+                self.skip_whitespace();
+                self.expect(b':', "`:`")?;
+                self.skip_whitespace();
+                match &*key {
+                    "_type" => {
+                        let (offset, ty) = self.string()?;
+                        if ty != "h" {
+                            return Err(JsonError::NotHistogram {
+                                offset,
+                                found: ty.into_owned(),
+                            });
+                        }
+                        is_histogram = true;
+                    }
+                    "_value" if histogram.is_none() => histogram = Some(self.bins()?),
+                    "_value" => return Err(self.error("a single `_value`")),
+                    _ => self.skip_value(0)?,
+                }
+                self.skip_whitespace();
+                if self.eat(b'}') {
+                    break;
+                }
+                self.expect(b',', "`,` or `}`")?;
+            }
+        }
+
+        if !is_histogram {
+            return Err(JsonError::MissingType);
+        }
+        histogram.ok_or(JsonError::MissingValue)
+    }
+
+    /// Parses an array of bin strings, starting at its opening `[`.
+    fn bins(&mut self) -> Result<Histogram, JsonError> {
+        self.expect(b'[', "`[`")?;
+        let mut histogram = Histogram::new();
+        self.skip_whitespace();
+        if self.eat(b']') {
+            return Ok(histogram);
+        }
+        for index in 0.. {
+            self.skip_whitespace();
+            let (offset, bin) = self.string()?;
+            histogram
+                .parse_bin(index, &bin, offset)
+                .map_err(JsonError::Bin)?;
+            self.skip_whitespace();
+            if self.eat(b']') {
+                break;
+            }
+            self.expect(b',', "`,` or `]`")?;
+        }
+        Ok(histogram)
+    }
+
+    /// Parses a string, returning its contents and the offset at which they
+    /// start.
+    fn string(&mut self) -> Result<(usize, Cow<'json, str>), JsonError> {
+        self.expect(b'"', "a string")?;
+        let start = self.pos;
+        // borrow the string from the input unless it contains escapes.
+        let mut unescaped = None::<String>;
+        let mut run_start = start;
+        loop {
+            match self.peek() {
+                Some(b'"') => {
+                    let run = &self.json[run_start..self.pos];
+                    self.pos += 1;
+                    let s = match unescaped {
+                        Some(mut s) => {
+                            s.push_str(run);
+                            Cow::Owned(s)
+                        }
+                        None => Cow::Borrowed(run),
+                    };
+                    return Ok((start, s));
+                }
+                Some(b'\\') => {
+                    let s = unescaped.get_or_insert_with(String::new);
+                    s.push_str(&self.json[run_start..self.pos]);
+                    self.pos += 1;
+                    let c = self.escape()?;
+                    s.push(c);
+                    run_start = self.pos;
+                }
+                Some(0x00..=0x1f) => return Err(self.error("`\"`")),
+                Some(_) => self.pos += 1,
+                None => return Err(self.error("`\"`")),
+            }
+        }
+    }
+
+    /// Parses the escape sequence following a `\`.
+    fn escape(&mut self) -> Result<char, JsonError> {
+        let c = match self.peek() {
+            Some(b'"') => '"',
+            Some(b'\\') => '\\',
+            Some(b'/') => '/',
+            Some(b'b') => '\u{8}',
+            Some(b'f') => '\u{c}',
+            Some(b'n') => '\n',
+            Some(b'r') => '\r',
+            Some(b't') => '\t',
+            Some(b'u') => {
+                self.pos += 1;
+                let offset = self.pos;
+                let hi = self.hex4()?;
+                let c = if (0xd800..0xdc00).contains(&hi) {
+                    // a high surrogate must be followed by a low surrogate.
+                    if !(self.eat(b'\\') && self.eat(b'u')) {
+                        return Err(self.error("a low surrogate"));
+                    }
+                    let lo = self.hex4()?;
+                    if !(0xdc00..0xe000).contains(&lo) {
+                        return Err(self.error("a low surrogate"));
+                    }
+                    0x10000 + ((hi - 0xd800) << 10) + (lo - 0xdc00)
+                } else {
+                    hi
+                };
+                return char::from_u32(c).ok_or(JsonError::Syntax {
+                    offset,
+                    expected: "a valid unicode escape",
+                });
+            }
+            _ => return Err(self.error("an escape sequence")),
+        };
+        self.pos += 1;
+        Ok(c)
+    }
+
+    /// Parses four hex digits.
+    fn hex4(&mut self) -> Result<u32, JsonError> {
+        let digits = self
+            .json
+            .get(self.pos..self.pos + 4)
+            .filter(|digits| digits.bytes().all(|b| b.is_ascii_hexdigit()))
+            .ok_or_else(|| self.error("four hex digits"))?;
+        self.pos += 4;
+        Ok(u32::from_str_radix(digits, 16).expect("digits are all hex"))
+    }
+
+    /// Skips over a value of any type, `depth` levels deep in the document.
+    fn skip_value(&mut self, depth: usize) -> Result<(), JsonError> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("less deeply nested values"));
+        }
+        let (open, close) = match self.peek() {
+            Some(b'"') => return self.string().map(drop),
+            Some(b'{') => (b'{', b'}'),
+            Some(b'[') => (b'[', b']'),
+            Some(b't') => return self.literal("true"),
+            Some(b'f') => return self.literal("false"),
+            Some(b'n') => return self.literal("null"),
+            Some(b'-' | b'0'..=b'9') => return self.number(),
+            _ => return Err(self.error("a value")),
+        };
+
+        self.expect(open, "a value")?;
+        self.skip_whitespace();
+        if self.eat(close) {
+            return Ok(());
+        }
+        loop {
+            self.skip_whitespace();
+            if open == b'{' {
+                self.string()?;
+                self.skip_whitespace();
+                self.expect(b':', "`:`")?;
+                self.skip_whitespace();
+            }
+            self.skip_value(depth + 1)?;
+            self.skip_whitespace();
+            if self.eat(close) {
+                return Ok(());
+            }
+            self.expect(
+                b',',
+                if open == b'{' {
+                    "`,` or `}`"
+                } else {
+                    "`,` or `]`"
+                },
+            )?;
+        }
+    }
+
+    fn literal(&mut self, literal: &'static str) -> Result<(), JsonError> {
+        if self.json[self.pos..].starts_with(literal) {
+            self.pos += literal.len();
+            Ok(())
+        } else {
+            Err(self.error(literal))
+        }
+    }
+
+    /// Skips over a number.
+    fn number(&mut self) -> Result<(), JsonError> {
+        self.eat(b'-');
+        if !self.eat(b'0') && self.digits() == 0 {
+            return Err(self.error("a digit"));
+        }
+        if self.eat(b'.') && self.digits() == 0 {
+            return Err(self.error("a digit"));
+        }
+        if self.eat(b'e') || self.eat(b'E') {
+            let _ = self.eat(b'+') || self.eat(b'-');
+            if self.digits() == 0 {
+                return Err(self.error("a digit"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Skips over ASCII digits, returning how many there were.
+    fn digits(&mut self) -> usize {
+        let start = self.pos;
+        while matches!(self.peek(), Some(b'0'..=b'9')) {
+            self.pos += 1;
+        }
+        self.pos - start
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.json.as_bytes().get(self.pos).copied()
+    }
+
+    /// Consumes `byte` if it's next, returning whether it was.
+    fn eat(&mut self, byte: u8) -> bool {
+        let is_next = self.peek() == Some(byte);
+        if is_next {
+            self.pos += 1;
+        }
+        is_next
+    }
+
+    fn expect(&mut self, byte: u8, expected: &'static str) -> Result<(), JsonError> {
+        if self.eat(byte) {
+            Ok(())
+        } else {
+            Err(self.error(expected))
+        }
+    }
+
+    fn error(&self, expected: &'static str) -> JsonError {
+        JsonError::Syntax {
+            offset: self.pos,
+            expected,
+        }
+    }
+}
+
+// === impl JsonError ===
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Syntax { offset, expected } => {
+                write!(
+                    f,
+                    "invalid histogram JSON at byte {offset}: expected {expected}"
+                )
+            }
+            Self::NotHistogram { offset, found } => write!(
+                f,
+                "JSON document has `_type` \"{found}\" at byte {offset}, expected \"h\""
+            ),
+            Self::MissingType => f.write_str("JSON histogram document has no `_type`"),
+            Self::MissingValue => f.write_str("JSON histogram document has no `_value`"),
+            Self::Bin(error) => fmt::Display::fmt(error, f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for JsonError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Bin(error) => Some(error),
+            _ => None,
+        }
+    }
+}
@@ -1,21 +1,86 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 extern crate alloc;
 
-use alloc::vec::Vec;
-use core::{convert::TryInto, fmt, str::FromStr};
+use alloc::{
+    collections::{binary_heap::PeekMut, BinaryHeap},
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{
+    cmp, fmt, iter,
+    ops::{Bound, RangeBounds},
+    str::FromStr,
+};
 
+#[cfg(target_has_atomic = "64")]
+mod atomic;
 mod bin;
-pub use bin::DisplayBin;
-use bin::{Bin, Bucket};
+mod codec;
+#[cfg(feature = "hdrhistogram")]
+mod hdr;
+mod json;
+mod native;
+mod otel;
+mod prometheus;
+#[cfg(feature = "serde")]
+mod serde_impl;
+mod sketch;
+#[cfg(target_has_atomic = "64")]
+pub use atomic::AtomicHistogram;
+use bin::Bucket;
+pub use bin::{Bin, BucketRef, DisplayBin, ParseErrorKind};
+pub use codec::DecodeError;
+#[cfg(feature = "hdrhistogram")]
+pub use hdr::HdrHistogramError;
+pub use json::JsonError;
+pub use native::{BucketSpan, NativeHistogram, NativeHistogramError};
+pub use otel::{ExponentialBuckets, ExponentialHistogramDataPoint, ExponentialHistogramError};
+pub use prometheus::{
+    BucketDistribution, PrecisionLoss, PrometheusBuckets, PrometheusError, PrometheusImport,
+    PrometheusParseError, PrometheusText,
+};
+pub use sketch::{Centroid, DDSketch, DDSketchError, TDigest, TDigestError};
 
 #[derive(Debug, Clone, Default)]
 pub struct Histogram {
     bins: alloc::vec::Vec<Bucket>,
+    policy: RecordPolicy,
 }
 
-#[derive(Debug, Eq, PartialEq)]
+/// An error returned when a value can't be recorded exactly.
+///
+/// These errors are only returned by histograms using
+/// [`RecordPolicy::Strict`].
+#[derive(Debug, Clone, PartialEq)]
 #[non_exhaustive]
-pub enum RecordError {}
+pub enum RecordError {
+    /// The value was NaN or infinite.
+    NotFinite(f64),
+    /// The value was too large in magnitude to have a bin.
+    Overflow(f64),
+    /// The value was too small in magnitude to have a bin, other than the
+    /// zero bin.
+    Underflow(f64),
+    /// Recording the value would increase the count of the bin `bin` past
+    /// `u64::MAX`.
+    CountSaturated { bin: Bin },
+}
+
+/// Selects what a histogram does with values that can't be recorded exactly.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum RecordPolicy {
+    /// Records every value, as the Go library and `libcircllhist` do.
+    ///
+    /// NaN and infinite values, and values too large in magnitude to have a
+    /// bin, are recorded in the NaN bin. Values too small in magnitude to have
+    /// a bin are recorded in the zero bin. Counts saturate at `u64::MAX`.
+    #[default]
+    Lenient,
+    /// Returns a [`RecordError`] rather than recording a value that can't be
+    /// recorded exactly, leaving the histogram unchanged.
+    Strict,
+}
 
 #[derive(Debug)]
 #[non_exhaustive]
@@ -24,6 +89,30 @@ pub enum QuantileError {
     OutOfBounds(f64),
 }
 
+/// Selects how quantiles are estimated from a histogram's bins.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum QuantileMethod {
+    /// Interpolates linearly between the lower and upper bounds of the bin
+    /// containing the quantile, according to the quantile's position in the
+    /// bin's cumulative count.
+    ///
+    /// This is the Go library's `ApproxQuantile` and `libcircllhist`'s
+    /// `hist_approx_quantile`. The 0 quantile is the lower bound of the lowest
+    /// bin, and the 1 quantile is the upper bound of the highest bin.
+    #[default]
+    Type1,
+    /// Estimates the "type 7" sample quantile used by default by numpy, R, and
+    /// Excel, as `libcircllhist`'s `hist_approx_quantile7` does.
+    ///
+    /// The `c` values in a bin are assumed to be evenly spaced inside it: the
+    /// `i`th value in a bin with lower bound `l` and width `w` is assumed to
+    /// be `l + w * (i + 1) / (c + 1)`. The quantile `q` of `n` values is then
+    /// interpolated between the values at ranks `floor((n - 1) * q)` and
+    /// `floor((n - 1) * q) + 1`.
+    Type7,
+}
+
 #[derive(Debug)]
 #[non_exhaustive]
 pub enum QuantilesError {
@@ -31,13 +120,66 @@ pub enum QuantilesError {
     NotSorted,
 }
 
-#[derive(Debug, Eq, PartialEq)]
+/// An error returned by [`Histogram::subtract`] and [`Histogram::delta`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum SubtractError {
+    /// The histogram being subtracted has `subtrahend` values in the bin
+    /// `bin`, but the histogram being subtracted from only has `available`
+    /// values in that bin.
+    ///
+    /// This usually means that the subtracted histogram is not an earlier
+    /// snapshot of the same cumulative histogram (for example, because the
+    /// process recording it restarted).
+    Underflow {
+        bin: Bin,
+        available: u64,
+        subtrahend: u64,
+    },
+}
+
+/// An error returned when parsing a histogram from bin strings such as
+/// `H[1.2e-01]=2`.
+#[derive(Debug, Clone, Eq, PartialEq)]
 #[non_exhaustive]
 pub struct ParseError {
-    bin: bin::ParseBinError,
-    i: usize,
+    kind: ParseErrorKind,
+    index: usize,
+    offset: usize,
+    token: String,
 }
 
+/// The count, mean, and sums of the 2nd, 3rd and 4th powers of the deviations
+/// from the mean of a set of values.
+#[cfg(feature = "std")]
+#[derive(Copy, Clone, Default)]
+struct CentralMoments {
+    count: f64,
+    mean: f64,
+    m2: f64,
+    m3: f64,
+    m4: f64,
+}
+
+/// A sum that tracks the rounding error of each addition (Neumaier's variant
+/// of Kahan summation), so that adding many values of very different
+/// magnitudes stays accurate.
+#[cfg(feature = "std")]
+#[derive(Default)]
+struct CompensatedSum {
+    sum: f64,
+    compensation: f64,
+}
+
+/// An entry in the heap used by [`Histogram::merge_all`].
+///
+/// This packs a bin's index and the index of the histogram it came from
+/// into a single integer, so that comparing entries is as cheap as possible.
+/// Since `BinaryHeap` is a max-heap, entries compare in reverse, so that the
+/// smallest bin is on top.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+struct HeapEntry(cmp::Reverse<u64>);
+
 impl Histogram {
     #[must_use]
     pub fn new() -> Self {
@@ -48,9 +190,31 @@ impl Histogram {
     pub fn with_capacity(bins: usize) -> Self {
         Self {
             bins: Vec::with_capacity(bins),
+            policy: RecordPolicy::default(),
+        }
+    }
+
+    /// Returns a new histogram that records values according to `policy`.
+    #[must_use]
+    pub fn with_policy(policy: RecordPolicy) -> Self {
+        Self {
+            bins: Vec::new(),
+            policy,
         }
     }
 
+    /// Returns this histogram's [`RecordPolicy`].
+    #[must_use]
+    pub fn policy(&self) -> RecordPolicy {
+        self.policy
+    }
+
+    /// Changes how this histogram records values that can't be recorded
+    /// exactly. Values that have already been recorded are unchanged.
+    pub fn set_policy(&mut self, policy: RecordPolicy) {
+        self.policy = policy;
+    }
+
     #[must_use]
     pub fn max(&self) -> f64 {
         self.quantile(1.0).unwrap_or(f64::NAN)
@@ -72,6 +236,13 @@ impl Histogram {
         self.bins.len()
     }
 
+    /// Returns `true` if no values have been recorded, or all have been
+    /// removed.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.bins.iter().all(|bucket| bucket.count == 0)
+    }
+
     /// Returns the number of bins in the histogram.
     #[must_use]
     pub fn bin_count(&self) -> usize {
@@ -94,8 +265,11 @@ impl Histogram {
         scale: i32,
         n: i64,
     ) -> Result<&mut Self, RecordError> {
-        self.insert(Bin::from_int_scale(val, scale), n);
-        Ok(self)
+        let bin = match self.policy {
+            RecordPolicy::Lenient => Bin::from_int_scale(val, scale),
+            RecordPolicy::Strict => Bin::try_from_int_scale(val, scale)?,
+        };
+        self.record_bin(bin, n)
     }
 
     /// Record a floating point value.
@@ -103,14 +277,60 @@ impl Histogram {
         self.record_f64s(val, 1)
     }
 
+    /// Records `n` occurances of a floating point value.
+    ///
+    /// If `n` is negative, `n` occurances are removed from the value's bin
+    /// instead, if it exists. To remove the values in one histogram from
+    /// another, use [`Histogram::subtract`].
     pub fn record_f64s(&mut self, val: f64, n: i64) -> Result<&mut Self, RecordError> {
-        self.insert(Bin::from_f64(val), n);
+        let bin = match self.policy {
+            RecordPolicy::Lenient => Bin::from_f64(val),
+            RecordPolicy::Strict => Bin::try_from_f64(val)?,
+        };
+        self.record_bin(bin, n)
+    }
+
+    fn record_bin(&mut self, bin: Bin, n: i64) -> Result<&mut Self, RecordError> {
+        if self.policy == RecordPolicy::Strict && n > 0 {
+            if let Ok(idx) = self.bins.binary_search_by_key(&bin, |bucket| bucket.bin) {
+                if self.bins[idx].count.checked_add(n as u64).is_none() {
+                    return Err(RecordError::CountSaturated { bin });
+                }
+            }
+        }
+        self.insert(bin, n);
         Ok(self)
     }
 
+    /// Returns the approximate values at the given quantiles (0..1), using
+    /// [`QuantileMethod::Type1`].
+    ///
+    /// The quantiles must be sorted in ascending order.
     pub fn approx_quantiles<const QUANTILES: usize>(
         &self,
         quantiles: &[f64; QUANTILES],
+    ) -> Result<[f64; QUANTILES], QuantilesError> {
+        self.approx_quantiles_with(QuantileMethod::Type1, quantiles)
+    }
+
+    /// Returns the approximate values at the given quantiles (0..1), using
+    /// the given [`QuantileMethod`].
+    ///
+    /// The quantiles must be sorted in ascending order.
+    pub fn approx_quantiles_with<const QUANTILES: usize>(
+        &self,
+        method: QuantileMethod,
+        quantiles: &[f64; QUANTILES],
+    ) -> Result<[f64; QUANTILES], QuantilesError> {
+        match method {
+            QuantileMethod::Type1 => self.approx_quantiles1(quantiles),
+            QuantileMethod::Type7 => self.approx_quantiles7(quantiles),
+        }
+    }
+
+    fn approx_quantiles1<const QUANTILES: usize>(
+        &self,
+        quantiles: &[f64; QUANTILES],
     ) -> Result<[f64; QUANTILES], QuantilesError> {
         // qOut := make([]float64, len(qIn))
         let mut out = [0.0; QUANTILES];
@@ -221,7 +441,17 @@ impl Histogram {
 
     /// Returns the recorded value at the given quantile (0..1).
     pub fn quantile(&self, quantile: f64) -> Result<f64, QuantileError> {
-        match self.approx_quantiles(&[quantile]) {
+        self.quantile_with(QuantileMethod::Type1, quantile)
+    }
+
+    /// Returns the recorded value at the given quantile (0..1), using the
+    /// given [`QuantileMethod`].
+    pub fn quantile_with(
+        &self,
+        method: QuantileMethod,
+        quantile: f64,
+    ) -> Result<f64, QuantileError> {
+        match self.approx_quantiles_with(method, &[quantile]) {
             Ok([q]) => Ok(q),
             Err(QuantilesError::NotSorted) => {
                 unreachable!("there's only one quantile, so it must be sorted")
@@ -230,6 +460,183 @@ impl Histogram {
         }
     }
 
+    fn approx_quantiles7<const QUANTILES: usize>(
+        &self,
+        quantiles: &[f64; QUANTILES],
+    ) -> Result<[f64; QUANTILES], QuantilesError> {
+        let mut out = [0.0; QUANTILES];
+        if QUANTILES == 0 {
+            return Ok(out);
+        }
+        if !is_sorted_by(quantiles, |q| *q) {
+            return Err(QuantilesError::NotSorted);
+        }
+        let total_count = self
+            .bins
+            .iter()
+            .filter_map(Bucket::count)
+            .fold(0u64, u64::saturating_add);
+        if total_count == 0 {
+            return Err(QuantilesError::Quantile(QuantileError::EmptyHistogram));
+        }
+
+        // the quantiles are sorted, so the ranks of both the lower and upper
+        // values we interpolate between only ever increase.
+        let mut lower = Samples::new(&self.bins);
+        let mut upper = Samples::new(&self.bins);
+        for (&q, out_q) in quantiles.iter().zip(out.iter_mut()) {
+            if !(0.0..=1.0).contains(&q) {
+                return Err(QuantilesError::Quantile(QuantileError::OutOfBounds(q)));
+            }
+            // the zero-based rank of the quantile, split into the rank of the
+            // value below it and the fraction of the way to the next value.
+            // with counts too large to be exact in an `f64`, the rank can round
+            // up past the last value.
+            let rank = (total_count - 1) as f64 * q;
+            let below = (rank as u64).min(total_count - 1);
+            let frac = if below == total_count - 1 {
+                0.0
+            } else {
+                (rank - below as f64).clamp(0.0, 1.0)
+            };
+
+            let lower_val = lower.value_at(below);
+            *out_q = if frac > 0.0 {
+                lower_val + frac * (upper.value_at(below + 1) - lower_val)
+            } else {
+                lower_val
+            };
+        }
+
+        Ok(out)
+    }
+
+    /// Returns the approximate fraction (0..1) of the recorded values that are
+    /// less than `value`.
+    ///
+    /// This is the inverse of [`Histogram::quantile`]. See
+    /// [`Histogram::approx_inverse_quantiles`] for details.
+    pub fn approx_inverse_quantile(&self, value: f64) -> Result<f64, QuantileError> {
+        match self.approx_inverse_quantiles(&[value]) {
+            Ok([q]) => Ok(q),
+            Err(QuantilesError::NotSorted) => {
+                unreachable!("there's only one value, so it must be sorted")
+            }
+            Err(QuantilesError::Quantile(e)) => Err(e),
+        }
+    }
+
+    /// Returns the approximate fraction (0..1) of the recorded values that are
+    /// less than each of `values`, which must be sorted.
+    ///
+    /// Values in bins entirely below a value are counted in full, and values
+    /// in the bin containing it are counted in proportion to where it falls in
+    /// the bin, assuming they are evenly spread through the bin. This matches
+    /// `libcircllhist`'s `hist_approx_inverse_quantile`. NaN values are not
+    /// counted, and passing a NaN value returns [`QuantileError::OutOfBounds`].
+    pub fn approx_inverse_quantiles<const VALUES: usize>(
+        &self,
+        values: &[f64; VALUES],
+    ) -> Result<[f64; VALUES], QuantilesError> {
+        let mut out = [0.0; VALUES];
+        if VALUES == 0 {
+            return Ok(out);
+        }
+        if !is_sorted_by(values, |v| *v) {
+            return Err(QuantilesError::NotSorted);
+        }
+        let total_count = sum_counts(&self.bins);
+        if total_count == 0 {
+            return Err(QuantilesError::Quantile(QuantileError::EmptyHistogram));
+        }
+
+        let mut bins = self
+            .bins
+            .iter()
+            .filter(|bucket| !bucket.bin.is_nan())
+            .peekable();
+        let mut lower_count = 0u64;
+        for (&value, out_q) in values.iter().zip(out.iter_mut()) {
+            if value.is_nan() {
+                return Err(QuantilesError::Quantile(QuantileError::OutOfBounds(value)));
+            }
+            // skip the bins that are entirely below the value...
+            while let Some(bucket) = bins.next_if(|bucket| bucket.bin.upper_bound() <= value) {
+                lower_count = lower_count.saturating_add(bucket.count);
+            }
+            // and interpolate within the bin containing it, if there is one.
+            let below = match bins.peek() {
+                Some(Bucket { bin, count }) if value > bin.left() => {
+                    lower_count as f64 + (value - bin.left()) / bin.bin_width() * *count as f64
+                }
+                _ => lower_count as f64,
+            };
+            *out_q = below / total_count as f64;
+        }
+
+        Ok(out)
+    }
+
+    /// Returns the number of values in bins that are entirely less than or
+    /// equal to `threshold`, as `libcircllhist`'s `hist_approx_count_below`
+    /// does.
+    ///
+    /// Values in the bin containing `threshold` are not counted.
+    #[must_use]
+    pub fn approx_count_below(&self, threshold: f64) -> u64 {
+        let end = self
+            .bins
+            .partition_point(|bucket| bucket.bin.is_nan() || bucket.bin.upper_bound() <= threshold);
+        sum_counts(&self.bins[..end])
+    }
+
+    /// Returns the number of values in bins that are entirely greater than or
+    /// equal to `threshold`, as `libcircllhist`'s `hist_approx_count_above`
+    /// does.
+    ///
+    /// Values in the bin containing `threshold` are not counted.
+    #[must_use]
+    pub fn approx_count_above(&self, threshold: f64) -> u64 {
+        if threshold.is_nan() {
+            return 0;
+        }
+        let start = self
+            .bins
+            .partition_point(|bucket| bucket.bin.is_nan() || bucket.bin.lower_bound() < threshold);
+        sum_counts(&self.bins[start..])
+    }
+
+    /// Returns the number of values in bins that are entirely between `lower`
+    /// and `upper` (inclusive).
+    ///
+    /// Values in the bins containing `lower` and `upper` are not counted.
+    #[must_use]
+    pub fn approx_count_between(&self, lower: f64, upper: f64) -> u64 {
+        if lower.is_nan() {
+            return 0;
+        }
+        let start = self
+            .bins
+            .partition_point(|bucket| bucket.bin.is_nan() || bucket.bin.lower_bound() < lower);
+        let end = self
+            .bins
+            .partition_point(|bucket| bucket.bin.is_nan() || bucket.bin.upper_bound() <= upper);
+        self.bins.get(start..end).map_or(0, sum_counts)
+    }
+
+    /// Returns the number of values in the bin that `value` falls into, as
+    /// `libcircllhist`'s `hist_approx_count_nearby` does.
+    ///
+    /// A NaN or out of range `value` falls into the NaN bin, so this returns
+    /// the number of NaN and out of range values that were recorded.
+    #[must_use]
+    pub fn count_nearby(&self, value: f64) -> u64 {
+        let bin = Bin::from_f64(value);
+        self.bins
+            .binary_search_by_key(&bin, |bucket| bucket.bin)
+            .map_or(0, |idx| self.bins[idx].count)
+    }
+
     // func (h *Histogram) ApproxMean() float64 {
     pub fn approx_mean(&self) -> f64 {
         // if h.useLocks {
@@ -283,19 +690,379 @@ impl Histogram {
         // return sum
     }
 
+    /// Returns the approximate `k`th raw moment of the recorded values (the
+    /// mean of each value raised to the power `k`), estimating each value as
+    /// its bin's midpoint, as `libcircllhist`'s `hist_approx_moment` does.
+    ///
+    /// NaN values are ignored. Returns NaN if no values have been recorded.
+    #[cfg(feature = "std")]
+    #[must_use]
+    pub fn approx_moment(&self, k: f64) -> f64 {
+        let mut count = 0.0;
+        let mut sum = CompensatedSum::default();
+        for (midpoint, cardinality) in self.midpoints() {
+            count += cardinality;
+            sum.add(midpoint.powf(k) * cardinality);
+        }
+        if count == 0.0 {
+            return f64::NAN;
+        }
+        sum.total() / count
+    }
+
+    /// Returns the approximate population variance of the recorded values.
+    ///
+    /// NaN values are ignored. Returns NaN if no values have been recorded.
+    #[cfg(feature = "std")]
+    #[must_use]
+    pub fn approx_variance(&self) -> f64 {
+        let moments = self.central_moments();
+        moments.m2 / moments.count
+    }
+
+    /// Returns the approximate population standard deviation of the recorded
+    /// values.
+    ///
+    /// NaN values are ignored. Returns NaN if no values have been recorded.
+    #[cfg(feature = "std")]
+    #[must_use]
+    pub fn approx_stddev(&self) -> f64 {
+        self.approx_variance().sqrt()
+    }
+
+    /// Returns the approximate population skewness of the recorded values.
+    ///
+    /// NaN values are ignored. Returns NaN if no values have been recorded, or
+    /// if they are all in the same bin.
+    #[cfg(feature = "std")]
+    #[must_use]
+    pub fn approx_skewness(&self) -> f64 {
+        let moments = self.central_moments();
+        moments.count.sqrt() * moments.m3 / moments.m2.powf(1.5)
+    }
+
+    /// Returns the approximate population excess kurtosis of the recorded
+    /// values, which is 0 for a normal distribution.
+    ///
+    /// NaN values are ignored. Returns NaN if no values have been recorded, or
+    /// if they are all in the same bin.
+    #[cfg(feature = "std")]
+    #[must_use]
+    pub fn approx_kurtosis(&self) -> f64 {
+        let moments = self.central_moments();
+        moments.count * moments.m4 / (moments.m2 * moments.m2) - 3.0
+    }
+
+    /// Returns the midpoint and count of each non-NaN bin with any values.
+    #[cfg(feature = "std")]
+    fn midpoints(&self) -> impl Iterator<Item = (f64, f64)> + '_ {
+        self.bins
+            .iter()
+            .filter(|bucket| bucket.count > 0)
+            .filter_map(|bucket| Some((bucket.bin.midpoint(), bucket.count()? as f64)))
+    }
+
+    /// Accumulates the central moments of the bins' midpoints in one pass.
+    ///
+    /// Rather than summing powers of the midpoints, which loses precision when
+    /// they span many orders of magnitude, each bin is merged into the running
+    /// moments of the bins before it, as a group of identical values (Chan et
+    /// al.'s pairwise update).
+    #[cfg(feature = "std")]
+    fn central_moments(&self) -> CentralMoments {
+        let mut moments = CentralMoments::default();
+        for (midpoint, n_b) in self.midpoints() {
+            let CentralMoments {
+                count: n_a,
+                mean,
+                m2,
+                m3,
+                m4,
+            } = moments;
+            let count = n_a + n_b;
+            let delta = midpoint - mean;
+            let delta_n = delta / count;
+            let term = delta * delta_n * n_a * n_b;
+            moments = CentralMoments {
+                count,
+                mean: mean + n_b * delta_n,
+                m2: m2 + term,
+                m3: m3 + term * delta_n * (n_a - n_b) - 3.0 * delta_n * n_b * m2,
+                m4: m4
+                    + term * delta_n * delta_n * (n_a * n_a - n_a * n_b + n_b * n_b)
+                    + 6.0 * delta_n * delta_n * n_b * n_b * m2
+                    - 4.0 * delta_n * n_b * m3,
+            };
+        }
+        moments
+    }
+
+    /// Adds all the values recorded in `other` to this histogram.
+    ///
+    /// Since both histograms' bins are sorted, this is a single linear merge
+    /// of the two, rather than an insertion of each of `other`'s bins.
     pub fn merge_from(&mut self, other: &Self) {
-        // the Go impl does a much more complicated thing, but this should also
-        // work...
-        for &Bucket { count, bin } in &other.bins {
-            let count = count.try_into().unwrap_or(i64::MAX);
-            self.insert(bin, count);
+        if other.bins.is_empty() {
+            return;
+        }
+        if self.bins.is_empty() {
+            self.bins.clone_from(&other.bins);
+            return;
+        }
+
+        let mut merged = Vec::with_capacity(self.bins.len() + other.bins.len());
+        merge_sorted(&self.bins, &other.bins, &mut merged);
+        self.bins = merged;
+    }
+
+    /// Removes all the values recorded in `other` from this histogram.
+    ///
+    /// Buckets whose count reaches zero are removed. If `other` contains more
+    /// values in any bin than this histogram does, this histogram is left
+    /// unchanged and an error is returned.
+    pub fn subtract(&mut self, other: &Self) -> Result<(), SubtractError> {
+        if !other.bins.is_empty() {
+            self.bins = self.subtracted(other)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the values recorded in this histogram but not in `earlier`.
+    ///
+    /// This is useful for computing the values recorded during an interval
+    /// from two snapshots of a cumulative histogram. See
+    /// [`Histogram::subtract`] for details.
+    pub fn delta(&self, earlier: &Self) -> Result<Self, SubtractError> {
+        Ok(Self {
+            bins: self.subtracted(earlier)?,
+            policy: self.policy,
+        })
+    }
+
+    /// Returns a copy of this histogram with reduced precision for values
+    /// with an exponent smaller than `min_exp`, as `libcircllhist`'s
+    /// `hist_compress_mbe` does.
+    ///
+    /// Values whose magnitude is less than `10^min_exp` are moved into the
+    /// zero bin, and values between `10^min_exp` and `10^(min_exp + 1)` are
+    /// moved into bins one tenth as precise (e.g. with a `min_exp` of -3,
+    /// `0.00123` is moved into the bin for `0.001`). Larger values and NaN
+    /// values are unchanged.
+    #[must_use]
+    pub fn compress_mbe(&self, min_exp: i8) -> Self {
+        let mut compressed = Self {
+            bins: Vec::with_capacity(self.bins.len()),
+            policy: self.policy,
+        };
+        // compressing a bin never moves it past another bin, so the
+        // compressed bins are still sorted, and equal bins are adjacent.
+        for &Bucket { bin, count } in self.bins.iter().filter(|bucket| bucket.count > 0) {
+            let bin = bin.with_min_exp(min_exp);
+            match compressed.bins.last_mut() {
+                Some(last) if last.bin == bin => last.count = last.count.saturating_add(count),
+                _ => compressed.bins.push(Bucket { bin, count }),
+            }
+        }
+        debug_assert!(is_sorted_by(&compressed.bins, |bucket| bucket.bin));
+        compressed
+    }
+
+    /// Returns a copy of this histogram with its values clamped to the range
+    /// `lower..=upper`, as `libcircllhist`'s `hist_clamp` does.
+    ///
+    /// Values in bins entirely below `lower` are moved into the bin containing
+    /// `lower`, and values in bins entirely above `upper` are moved into the
+    /// bin containing `upper`. Values in the bins containing `lower` and
+    /// `upper` are left where they are, since only their bin is known. NaN
+    /// values are kept.
+    ///
+    /// Infinite bounds, and bounds too large in magnitude to have a bin, are
+    /// treated as the largest bin with the same sign.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `lower > upper`, or if either bound is NaN.
+    #[must_use]
+    pub fn clamp(&self, lower: f64, upper: f64) -> Self {
+        assert!(lower <= upper, "invalid clamp bounds {lower}..={upper}");
+        let (lower, upper) = (bound_bin(lower), bound_bin(upper));
+        let nans = self.bins.partition_point(|bucket| bucket.bin.is_nan());
+        let start = self.bins.partition_point(|bucket| bucket.bin < lower);
+        let end = self.bins.partition_point(|bucket| bucket.bin <= upper);
+
+        let mut clamped = Self {
+            bins: Vec::with_capacity(nans + (end - start) + 2),
+            policy: self.policy,
+        };
+        clamped.bins.extend_from_slice(&self.bins[..nans]);
+        clamped.bins.extend_from_slice(&self.bins[start..end]);
+        for (bin, bins) in [(lower, &self.bins[nans..start]), (upper, &self.bins[end..])] {
+            let count = sum_counts(bins);
+            if count > 0 {
+                clamped.insert_bucket(Bucket { bin, count });
+            }
+        }
+        clamped
+    }
+
+    /// Removes the values in bins entirely outside `range`.
+    ///
+    /// Values in the bins containing the ends of `range` are kept, whether the
+    /// end is included in `range` or not, since only their bin is known. NaN
+    /// values are removed, since they are not in any range.
+    ///
+    /// Infinite ends of `range`, and ends too large in magnitude to have a
+    /// bin, are treated as the largest bin with the same sign.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either end of `range` is NaN.
+    pub fn retain_range(&mut self, range: impl RangeBounds<f64>) {
+        let start = match range.start_bound() {
+            Bound::Included(&lower) | Bound::Excluded(&lower) => {
+                let lower = bound_bin(lower);
+                self.bins.partition_point(|bucket| bucket.bin < lower)
+            }
+            Bound::Unbounded => self.bins.partition_point(|bucket| bucket.bin.is_nan()),
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&upper) | Bound::Excluded(&upper) => {
+                let upper = bound_bin(upper);
+                self.bins.partition_point(|bucket| bucket.bin <= upper)
+            }
+            Bound::Unbounded => self.bins.len(),
+        };
+        self.bins.truncate(end.max(start));
+        self.bins.drain(..start);
+    }
+
+    fn subtracted(&self, other: &Self) -> Result<Vec<Bucket>, SubtractError> {
+        let underflow = |bucket: &Bucket, available| SubtractError::Underflow {
+            bin: bucket.bin,
+            available,
+            subtrahend: bucket.count,
+        };
+        let mut theirs = other
+            .bins
+            .iter()
+            .filter(|bucket| bucket.count > 0)
+            .peekable();
+        let mut bins = Vec::with_capacity(self.bins.len());
+        for &Bucket { bin, mut count } in &self.bins {
+            while let Some(subtrahend) = theirs.next_if(|theirs| theirs.bin <= bin) {
+                // if `other`'s bin is less than this one, it isn't present in
+                // this histogram.
+                if subtrahend.bin != bin {
+                    return Err(underflow(subtrahend, 0));
+                }
+                count = count
+                    .checked_sub(subtrahend.count)
+                    .ok_or_else(|| underflow(subtrahend, count))?;
+            }
+            if count > 0 {
+                bins.push(Bucket { bin, count });
+            }
+        }
+
+        // any bins left in `other` are greater than every bin in this
+        // histogram.
+        if let Some(subtrahend) = theirs.next() {
+            return Err(underflow(subtrahend, 0));
+        }
+
+        Ok(bins)
+    }
+
+    /// Returns a histogram containing all the values recorded in
+    /// `histograms`.
+    ///
+    /// This performs a single k-way merge of every histogram's bins, rather
+    /// than repeatedly calling [`Histogram::merge_from`], so each bin is
+    /// copied once no matter how many histograms are merged. Counts saturate
+    /// at `u64::MAX`.
+    ///
+    /// The result's [`RecordPolicy`] is [`RecordPolicy::Strict`] if any of the
+    /// histograms' is, and the default otherwise.
+    #[must_use]
+    pub fn merge_all<'a>(histograms: impl IntoIterator<Item = &'a Histogram>) -> Self {
+        let mut policy = RecordPolicy::default();
+        let sources = histograms
+            .into_iter()
+            .inspect(|histogram| {
+                if histogram.policy == RecordPolicy::Strict {
+                    policy = RecordPolicy::Strict;
+                }
+            })
+            .map(|histogram| &histogram.bins[..])
+            .filter(|bins| !bins.is_empty())
+            .collect::<Vec<_>>();
+        match sources[..] {
+            [] => return Self::with_policy(policy),
+            [bins] => {
+                return Self {
+                    bins: bins.to_vec(),
+                    policy,
+                }
+            }
+            _ => {}
+        }
+
+        // the heap contains the next bin from each source that still has bins
+        // left, along with the index of that source.
+        let mut heap = BinaryHeap::with_capacity(sources.len());
+        for (i, bins) in sources.iter().enumerate() {
+            heap.push(HeapEntry::new(bins[0].bin, i));
+        }
+        let mut positions = alloc::vec![0; sources.len()];
+        let max_len = sources.iter().map(|bins| bins.len()).max().unwrap_or(0);
+        let mut bins: Vec<Bucket> = Vec::with_capacity(max_len);
+
+        while let Some(mut top) = heap.peek_mut() {
+            let i = top.source();
+            let Bucket { bin, count } = sources[i][positions[i]];
+            match bins.last_mut() {
+                Some(last) if last.bin == bin => last.count = last.count.saturating_add(count),
+                _ => bins.push(Bucket { bin, count }),
+            }
+
+            positions[i] += 1;
+            match sources[i].get(positions[i]) {
+                Some(next) => *top = HeapEntry::new(next.bin, i),
+                None => {
+                    PeekMut::pop(top);
+                }
+            }
         }
+
+        Self { bins, policy }
+    }
+
+    /// Returns the buckets in this histogram, in ascending order of their bins.
+    ///
+    /// The NaN bin, if present, comes first.
+    pub fn buckets(&self) -> impl Iterator<Item = BucketRef<'_>> + '_ {
+        self.bins.iter().map(BucketRef)
     }
 
     pub fn display_bins(&self) -> impl Iterator<Item = DisplayBin<'_>> + '_ {
-        self.bins.iter().map(DisplayBin)
+        self.bins.iter().map(DisplayBin::new)
+    }
+
+    // func (h *Histogram) DecStrings() []string {
+    /// Returns a bin string for each bin in this histogram, formatted exactly
+    /// as the Go library's `DecStrings` does (e.g. `H[1.2e-01]=2`).
+    #[must_use]
+    pub fn dec_strings(&self) -> Vec<String> {
+        self.display_bins()
+            .map(|bin| bin.canonical().to_string())
+            .collect()
     }
 
+    /// Parses a histogram from a list of bin strings, such as
+    /// `["H[0.0e+00]=1", "H[1.2e-01]=2"]`, stopping at the first invalid one.
+    ///
+    /// Bin strings for the same bin are coalesced. Offsets in the returned
+    /// [`ParseError`] are relative to the invalid bin string.
     pub fn from_strs<A: AsRef<str>>(
         strs: impl IntoIterator<Item = A>,
     ) -> Result<Histogram, ParseError> {
@@ -311,12 +1078,134 @@ impl Histogram {
         // coalesced.
         let mut histogram = Self::with_capacity(sz);
         for (i, bin) in strs.enumerate() {
-            let (bin, count) = Bin::from_str(bin.as_ref()).map_err(|bin| ParseError { bin, i })?;
-            histogram.insert(bin, count);
+            histogram.parse_bin(i, bin.as_ref(), 0)?;
         }
         Ok(histogram)
     }
 
+    /// Parses a histogram from a list of bin strings, skipping invalid ones.
+    ///
+    /// Returns the histogram of the valid bin strings, and an error for each
+    /// invalid one. See [`Histogram::from_strs`] for details.
+    pub fn from_strs_lenient<A: AsRef<str>>(
+        strs: impl IntoIterator<Item = A>,
+    ) -> (Histogram, Vec<ParseError>) {
+        let mut histogram = Self::new();
+        let errors = strs
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, bin)| histogram.parse_bin(i, bin.as_ref(), 0).err())
+            .collect();
+        (histogram, errors)
+    }
+
+    /// Parses a histogram from a string of comma- or newline-separated bin
+    /// strings, skipping invalid ones.
+    ///
+    /// Returns the histogram of the valid bin strings, and an error for each
+    /// invalid one. Offsets in the errors are relative to the start of `s`.
+    pub fn from_str_lenient(s: &str) -> (Histogram, Vec<ParseError>) {
+        let mut histogram = Self::new();
+        let errors = split_bin_strs(s)
+            .enumerate()
+            .filter_map(|(i, (offset, bin))| histogram.parse_bin(i, bin, offset).err())
+            .collect();
+        (histogram, errors)
+    }
+
+    /// Parses the bin string `bin`, the `index`th in the input, which starts
+    /// `offset` bytes into the input, and adds it to this histogram.
+    fn parse_bin(&mut self, index: usize, bin: &str, offset: usize) -> Result<(), ParseError> {
+        let (bin, count) = Bin::from_str(bin).map_err(|(kind, bin_offset)| ParseError {
+            kind,
+            index,
+            offset: offset + bin_offset,
+            token: bin.trim().into(),
+        })?;
+        self.insert(bin, count);
+        Ok(())
+    }
+
+    /// Adds `bucket`'s count to the bucket for the same bin, or inserts it if
+    /// there is no such bucket.
+    ///
+    /// Unlike `insert`, this takes an unsigned count, so counts greater than
+    /// `i64::MAX` are added without being clamped.
+    fn insert_bucket(&mut self, bucket: Bucket) {
+        debug_assert!(is_sorted_by(&self.bins, |bucket| bucket.bin));
+        match self
+            .bins
+            .binary_search_by_key(&bucket.bin, |bucket| bucket.bin)
+        {
+            Ok(idx) => {
+                let count = &mut self.bins[idx].count;
+                *count = count.saturating_add(bucket.count);
+            }
+            Err(idx) => self.bins.insert(idx, bucket),
+        }
+    }
+
+    /// Adds `count` values in the interval `(lower, upper]` to this
+    /// histogram, distributed over the bins it overlaps according to
+    /// `distribution`, and returns the number of bins it overlaps.
+    fn insert_interval(
+        &mut self,
+        lower: f64,
+        upper: f64,
+        count: u64,
+        distribution: BucketDistribution,
+    ) -> usize {
+        // the bins overlapping the interval, and the width of each overlap.
+        let first = bound_bin(lower).index();
+        let last = bound_bin(upper).index();
+        let overlaps: Vec<(Bin, f64)> = (first..=last)
+            .filter_map(Bin::from_index)
+            .map(|bin| {
+                let overlap = upper.min(bin.upper_bound()) - lower.max(bin.lower_bound());
+                (bin, overlap.max(0.0))
+            })
+            .filter(|&(_, overlap)| overlap > 0.0)
+            .collect();
+
+        let mut insert = |bin, count| self.insert_bucket(Bucket { bin, count });
+        match (distribution, &overlaps[..]) {
+            (_, []) => insert(bound_bin(upper), count),
+            (_, &[(bin, _)]) => insert(bin, count),
+            (BucketDistribution::Uniform, overlaps) => {
+                // round the cumulative count rather than each bin's, so that the
+                // counts add up to exactly `count`.
+                let width: f64 = overlaps.iter().map(|&(_, overlap)| overlap).sum();
+                let (mut covered, mut assigned) = (0.0, 0);
+                for (i, &(bin, overlap)) in overlaps.iter().enumerate() {
+                    covered += overlap;
+                    let cumulative = if i == overlaps.len() - 1 {
+                        count
+                    } else {
+                        // rounded to the nearest integer without `f64::round`,
+                        // which needs `std`.
+                        let share = count as f64 * (covered / width);
+                        let whole = share as u64;
+                        let rounded = whole + u64::from(share - whole as f64 >= 0.5);
+                        rounded.clamp(assigned, count)
+                    };
+                    if cumulative > assigned {
+                        insert(bin, cumulative - assigned);
+                    }
+                    assigned = cumulative;
+                }
+            }
+            (BucketDistribution::Midpoint, _) => {
+                insert(bound_bin(lower + (upper - lower) / 2.0), count)
+            }
+            (BucketDistribution::UpperBound, overlaps) => match bound_bin(upper) {
+                // the zero bin has no width, so it never overlaps the interval.
+                bin if bin == Bin::ZERO => insert(bin, count),
+                _ => insert(overlaps[overlaps.len() - 1].0, count),
+            },
+        }
+        overlaps.len()
+    }
+
     fn insert(&mut self, bin: Bin, count: i64) {
         debug_assert!(is_sorted_by(&self.bins, |bucket| bucket.bin));
         match self.bins.binary_search_by_key(&bin, |bucket| bucket.bin) {
@@ -357,16 +1246,199 @@ impl Histogram {
 }
 
 impl PartialEq for Histogram {
+    /// Histograms are equal if they have the same count in every bin. Bins with
+    /// a count of zero are ignored, as they are when serializing.
     fn eq(&self, other: &Self) -> bool {
+        let nonzero = |bucket: &&Bucket| bucket.count > 0;
         self.bins
             .iter()
-            .zip(other.bins.iter())
-            .all(|(my_bin, their_bin)| my_bin == their_bin)
+            .filter(nonzero)
+            .eq(other.bins.iter().filter(nonzero))
     }
 }
 
 impl Eq for Histogram {}
 
+/// Walks the values in a histogram's (non-NaN) bins in ascending order, for
+/// type 7 quantile estimation.
+struct Samples<'a> {
+    bins: core::slice::Iter<'a, Bucket>,
+    current: Bucket,
+    /// The number of values in the bins before `current`.
+    lower_count: u64,
+}
+
+// === impl Samples ===
+
+impl<'a> Samples<'a> {
+    fn new(bins: &'a [Bucket]) -> Self {
+        Self {
+            bins: bins.iter(),
+            current: Bucket {
+                bin: Bin::ZERO,
+                count: 0,
+            },
+            lower_count: 0,
+        }
+    }
+
+    /// Returns the estimated value with the zero-based rank `rank`, which
+    /// must not be less than any rank previously passed to this method.
+    fn value_at(&mut self, rank: u64) -> f64 {
+        while self.lower_count.saturating_add(self.current.count) <= rank {
+            self.lower_count = self.lower_count.saturating_add(self.current.count);
+            self.current = *self
+                .bins
+                .find(|bucket| !bucket.bin.is_nan())
+                .expect("rank must be less than the total count");
+        }
+        // the values in a bin are assumed to be evenly spaced inside it.
+        let Bucket { bin, count } = self.current;
+        let position = ((rank - self.lower_count) as f64 + 1.0) / (count as f64 + 1.0);
+        bin.left() + position * bin.bin_width()
+    }
+}
+
+// === impl CompensatedSum ===
+
+#[cfg(feature = "std")]
+impl CompensatedSum {
+    fn add(&mut self, val: f64) {
+        let sum = self.sum + val;
+        if self.sum.abs() >= val.abs() {
+            self.compensation += (self.sum - sum) + val;
+        } else {
+            self.compensation += (val - sum) + self.sum;
+        }
+        self.sum = sum;
+    }
+
+    fn total(&self) -> f64 {
+        self.sum + self.compensation
+    }
+}
+
+// === impl HeapEntry ===
+
+impl HeapEntry {
+    fn new(bin: Bin, source: usize) -> Self {
+        debug_assert!(source <= u32::MAX as usize);
+        Self(cmp::Reverse(u64::from(bin.index()) << 32 | source as u64))
+    }
+
+    fn source(&self) -> usize {
+        (self.0 .0 & 0xffff_ffff) as usize
+    }
+}
+
+// === impl RecordError ===
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFinite(val) => write!(f, "cannot record non-finite value {val}"),
+            Self::Overflow(val) => write!(f, "value {val:e} is too large to record"),
+            Self::Underflow(val) => write!(f, "value {val:e} is too small to record"),
+            Self::CountSaturated { bin } => {
+                write!(
+                    f,
+                    "count of bin {:e} would exceed the maximum count",
+                    bin.value()
+                )
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RecordError {}
+
+// === impl ParseError ===
+
+impl ParseError {
+    /// Returns the reason the bin string was invalid.
+    #[must_use]
+    pub fn kind(&self) -> &ParseErrorKind {
+        &self.kind
+    }
+
+    /// Returns the index of the invalid bin string in the input.
+    #[must_use]
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the byte offset in the input at which the problem was found.
+    #[must_use]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the invalid bin string, without surrounding whitespace.
+    #[must_use]
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid bin {} `{}` at byte {}: {}",
+            self.index, self.token, self.offset, self.kind
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            ParseErrorKind::ParseCount(e) => Some(e),
+            ParseErrorKind::ParseBin(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+// === impl SubtractError ===
+
+impl fmt::Display for SubtractError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Underflow {
+                bin,
+                available,
+                subtrahend,
+            } => write!(
+                f,
+                "cannot subtract {subtrahend} values from bin {:e}, which has {available} values",
+                bin.value()
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SubtractError {}
+
+impl<'a> iter::FromIterator<&'a Histogram> for Histogram {
+    /// Merges the histograms as by [`Histogram::merge_all`], including its
+    /// choice of [`RecordPolicy`].
+    fn from_iter<I: IntoIterator<Item = &'a Histogram>>(histograms: I) -> Self {
+        Self::merge_all(histograms)
+    }
+}
+
+impl<'a> iter::Sum<&'a Histogram> for Histogram {
+    /// Merges the histograms as by [`Histogram::merge_all`], including its
+    /// choice of [`RecordPolicy`].
+    fn sum<I: Iterator<Item = &'a Histogram>>(histograms: I) -> Self {
+        Self::merge_all(histograms)
+    }
+}
+
 impl fmt::Display for Histogram {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let delim = if f.alternate() { "\n" } else { ", " };
@@ -385,12 +1457,76 @@ impl fmt::Display for Histogram {
 
 impl FromStr for Histogram {
     type Err = ParseError;
+    /// Parses a histogram from a string of comma- or newline-separated bin
+    /// strings, stopping at the first invalid one.
+    ///
+    /// Offsets in the returned [`ParseError`] are relative to the start of
+    /// `s`.
     fn from_str(s: &str) -> Result<Self, ParseError> {
-        let strs = s.trim().split(|c| c == ',' || c == '\n');
-        Self::from_strs(strs)
+        let mut histogram = Self::new();
+        for (i, (offset, bin)) in split_bin_strs(s).enumerate() {
+            histogram.parse_bin(i, bin, offset)?;
+        }
+        Ok(histogram)
     }
 }
 
+/// Merges two sorted lists of buckets into `out`, adding the counts of buckets
+/// for the same bin.
+fn merge_sorted(a: &[Bucket], b: &[Bucket], out: &mut Vec<Bucket>) {
+    debug_assert!(is_sorted_by(a, |bucket| bucket.bin));
+    debug_assert!(is_sorted_by(b, |bucket| bucket.bin));
+    let (mut a, mut b) = (a.iter().peekable(), b.iter().peekable());
+    while let (Some(&&x), Some(&&y)) = (a.peek(), b.peek()) {
+        match x.bin.cmp(&y.bin) {
+            cmp::Ordering::Less => {
+                out.push(x);
+                a.next();
+            }
+            cmp::Ordering::Greater => {
+                out.push(y);
+                b.next();
+            }
+            cmp::Ordering::Equal => {
+                out.push(Bucket {
+                    bin: x.bin,
+                    count: x.count.saturating_add(y.count),
+                });
+                a.next();
+                b.next();
+            }
+        }
+    }
+    out.extend(a.chain(b));
+}
+
+/// Returns the bin containing `bound`, or the lowest or highest bin if it's
+/// too large in magnitude to have one.
+fn bound_bin(bound: f64) -> Bin {
+    assert!(!bound.is_nan(), "bounds must not be NaN");
+    match Bin::for_value(bound) {
+        bin if !bin.is_nan() => bin,
+        _ if bound < 0.0 => Bin::from_index(1).expect("the lowest bin"),
+        _ => Bin::from_index(Bin::COUNT - 1).expect("the highest bin"),
+    }
+}
+
+/// Splits a string of comma- or newline-separated bin strings, returning each
+/// bin string and its byte offset in `s`.
+fn split_bin_strs(s: &str) -> impl Iterator<Item = (usize, &str)> {
+    s.trim()
+        .split(|c| c == ',' || c == '\n')
+        .map(move |bin| (bin.as_ptr() as usize - s.as_ptr() as usize, bin))
+}
+
+/// Returns the total count of the non-NaN buckets in `bins`, saturating at
+/// `u64::MAX`.
+fn sum_counts(bins: &[Bucket]) -> u64 {
+    bins.iter()
+        .filter_map(Bucket::count)
+        .fold(0, u64::saturating_add)
+}
+
 fn is_sorted_by<T, U: PartialOrd>(slice: impl AsRef<[T]>, f: impl Fn(&T) -> U) -> bool {
     slice.as_ref().windows(2).all(|w| f(&w[0]) <= f(&w[1]))
 }
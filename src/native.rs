@@ -0,0 +1,300 @@
+//! Conversion to and from Prometheus native histograms.
+//!
+//! Native (or "sparse") histograms use the same exponential buckets as
+//! OpenTelemetry's exponential histograms, with the `schema` playing the role
+//! of the scale, except that bucket `index` holds the values whose magnitude is
+//! in the interval `(base^(index - 1), base^index]`. Rather than a dense array
+//! of counts, the non-empty buckets are described by spans of consecutive
+//! indices, and their counts are delta-encoded. See
+//! <https://prometheus.io/docs/specs/native_histograms/>.
+#[cfg(feature = "std")]
+use crate::{
+    bin::Bucket, Bin, BucketDistribution, ExponentialBuckets, ExponentialHistogramDataPoint,
+    ExponentialHistogramError, Histogram,
+};
+#[cfg(feature = "std")]
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+/// A Prometheus native histogram with integer counts, shaped like the
+/// `Histogram` message of Prometheus' remote write protocol.
+///
+/// The timestamp and reset hint are not included.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NativeHistogram {
+    /// The number of values, including NaN values, which are in no bucket.
+    pub count: u64,
+    /// The sum of the values.
+    pub sum: f64,
+    /// The resolution of the buckets, from [`NativeHistogram::MIN_SCHEMA`] to
+    /// [`NativeHistogram::MAX_SCHEMA`].
+    pub schema: i32,
+    /// The largest magnitude of the values counted by `zero_count`.
+    pub zero_threshold: f64,
+    /// The number of values whose magnitude is at most `zero_threshold`.
+    pub zero_count: u64,
+    /// The spans of indices of the non-empty buckets for negative values.
+    pub negative_spans: Vec<BucketSpan>,
+    /// The count of the first bucket in `negative_spans`, followed by the
+    /// difference between each bucket's count and the one before it.
+    pub negative_deltas: Vec<i64>,
+    /// The spans of indices of the non-empty buckets for positive values.
+    pub positive_spans: Vec<BucketSpan>,
+    /// The count of the first bucket in `positive_spans`, followed by the
+    /// difference between each bucket's count and the one before it.
+    pub positive_deltas: Vec<i64>,
+}
+
+/// A run of consecutive bucket indices in a [`NativeHistogram`].
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct BucketSpan {
+    /// The index of the first bucket in the span for the first span, or the
+    /// number of indices skipped since the end of the previous span.
+    pub offset: i32,
+    /// The number of buckets in the span.
+    pub length: u32,
+}
+
+/// An error returned when converting a [`NativeHistogram`] into a
+/// [`Histogram`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum NativeHistogramError {
+    /// The schema is not one of the exponential schemas Prometheus supports.
+    InvalidSchema(i32),
+    /// The spans cover `spans` buckets, but there are `deltas` deltas.
+    SpanMismatch { spans: u64, deltas: usize },
+    /// The counts decoded from the deltas include a negative count, or
+    /// overflow an `i64`, at bucket `index`.
+    InvalidCount { index: i64 },
+    /// A span after the first has a negative offset, going back to bucket
+    /// `index`, or the spans reach an index too large in magnitude for any
+    /// bin.
+    InvalidIndex { index: i64 },
+}
+
+// the conversions go through exponential histograms, which are only
+// available with `std`.
+#[cfg(feature = "std")]
+impl Histogram {
+    /// Returns this histogram as a Prometheus native histogram with the given
+    /// schema.
+    ///
+    /// Each bin's values are counted in the bucket containing its midpoint.
+    /// The `zero_threshold` is Prometheus' default,
+    /// [`NativeHistogram::DEFAULT_ZERO_THRESHOLD`], and the zero bin's values,
+    /// and those of any bins whose midpoints are no larger in magnitude, are
+    /// counted in `zero_count`. As
+    /// Prometheus does for NaN observations, values in the NaN bin are counted
+    /// in `count` but in no bucket, and make `sum` NaN. The sum is otherwise
+    /// estimated from the bins' midpoints, as by [`Histogram::approx_sum`].
+    ///
+    /// Schema 7 and up have buckets no wider, relative to their values, than
+    /// any bin. Lower schemas produce fewer buckets, each spanning several
+    /// bins.
+    ///
+    /// # Panics
+    ///
+    /// If `schema` is not between [`NativeHistogram::MIN_SCHEMA`] and
+    /// [`NativeHistogram::MAX_SCHEMA`].
+    #[must_use]
+    pub fn to_native_histogram(&self, schema: i32) -> NativeHistogram {
+        assert!(
+            (NativeHistogram::MIN_SCHEMA..=NativeHistogram::MAX_SCHEMA).contains(&schema),
+            "invalid native histogram schema {schema}"
+        );
+
+        let point = self.to_exponential_histogram_with_threshold(
+            schema,
+            NativeHistogram::DEFAULT_ZERO_THRESHOLD,
+        );
+        let (negative_spans, negative_deltas) = encode_buckets(&point.negative);
+        let (positive_spans, positive_deltas) = encode_buckets(&point.positive);
+        let nan_count = self
+            .bins
+            .first()
+            .filter(|bucket| bucket.bin.is_nan())
+            .map_or(0, |bucket| bucket.count);
+        NativeHistogram {
+            count: point.count.saturating_add(nan_count),
+            sum: self.approx_sum(),
+            schema,
+            zero_threshold: point.zero_threshold,
+            zero_count: point.zero_count,
+            negative_spans,
+            negative_deltas,
+            positive_spans,
+            positive_deltas,
+        }
+    }
+
+    /// Converts a Prometheus native histogram into a histogram.
+    ///
+    /// Each bucket's values are distributed over the bins its interval overlaps
+    /// according to `distribution`, as by
+    /// [`Histogram::from_exponential_histogram`]. The zero bucket mostly holds
+    /// exact zeros, so its values are recorded in the zero bin, whatever the
+    /// `zero_threshold`. The histogram's count and sum are ignored.
+    pub fn from_native_histogram(
+        native: &NativeHistogram,
+        distribution: BucketDistribution,
+    ) -> Result<Self, NativeHistogramError> {
+        if !(NativeHistogram::MIN_SCHEMA..=NativeHistogram::MAX_SCHEMA).contains(&native.schema) {
+            return Err(NativeHistogramError::InvalidSchema(native.schema));
+        }
+        let point = ExponentialHistogramDataPoint {
+            scale: native.schema,
+            negative: decode_buckets(&native.negative_spans, &native.negative_deltas)?,
+            positive: decode_buckets(&native.positive_spans, &native.positive_deltas)?,
+            ..ExponentialHistogramDataPoint::default()
+        };
+        let mut histogram = Histogram::from_exponential_histogram(&point, distribution).map_err(
+            |ExponentialHistogramError::InvalidScale(schema)| {
+                NativeHistogramError::InvalidSchema(schema)
+            },
+        )?;
+        if native.zero_count > 0 {
+            histogram.insert_bucket(Bucket {
+                bin: Bin::ZERO,
+                count: native.zero_count,
+            });
+        }
+        Ok(histogram)
+    }
+}
+
+/// Returns the spans and deltas of the non-empty buckets in `buckets`.
+#[cfg(feature = "std")]
+fn encode_buckets(buckets: &ExponentialBuckets) -> (Vec<BucketSpan>, Vec<i64>) {
+    let mut spans = Vec::<BucketSpan>::new();
+    let mut deltas = Vec::new();
+    // the index after the end of the last span, and the last bucket's count.
+    let (mut end, mut prev) = (0, 0i64);
+    for (index, count) in buckets.iter().filter(|&(_, count)| count > 0) {
+        // Prometheus' buckets are one index higher than OpenTelemetry's.
+        let index = index + 1;
+        match spans.last_mut() {
+            Some(span) if index == end => span.length += 1,
+            _ => spans.push(BucketSpan {
+                offset: (if spans.is_empty() { index } else { index - end }) as i32,
+                length: 1,
+            }),
+        }
+        end = index + 1;
+        // counts are at most `i64::MAX`, other than in a histogram merged from
+        // enormous ones.
+        let count = i64::try_from(count).unwrap_or(i64::MAX);
+        deltas.push(count - prev);
+        prev = count;
+    }
+    (spans, deltas)
+}
+
+/// Returns the dense buckets described by `spans` and `deltas`.
+#[cfg(feature = "std")]
+fn decode_buckets(
+    spans: &[BucketSpan],
+    deltas: &[i64],
+) -> Result<ExponentialBuckets, NativeHistogramError> {
+    let len: u64 = spans.iter().map(|span| u64::from(span.length)).sum();
+    if len != deltas.len() as u64 {
+        return Err(NativeHistogramError::SpanMismatch {
+            spans: len,
+            deltas: deltas.len(),
+        });
+    }
+
+    // the indices of the buckets and their counts.
+    let mut buckets = Vec::with_capacity(deltas.len());
+    let mut deltas = deltas.iter();
+    let (mut index, mut count) = (0i64, 0i64);
+    for (i, span) in spans.iter().enumerate() {
+        index = if i == 0 {
+            i64::from(span.offset)
+        } else if span.offset < 0 {
+            // only the first span may start before the end of the last.
+            return Err(NativeHistogramError::InvalidIndex {
+                index: index + i64::from(span.offset),
+            });
+        } else {
+            index + i64::from(span.offset)
+        };
+        for _ in 0..span.length {
+            // an index this large is beyond the largest bin at every schema,
+            // and limits the size of the dense bucket array.
+            if (index - 1).abs() > MAX_INDEX {
+                return Err(NativeHistogramError::InvalidIndex { index });
+            }
+            let delta = deltas.next().expect("checked the number of deltas");
+            count = count
+                .checked_add(*delta)
+                .filter(|&count| count >= 0)
+                .ok_or(NativeHistogramError::InvalidCount { index })?;
+            buckets.push((index, count as u64));
+            index += 1;
+        }
+    }
+
+    // the spans are in ascending order, so the buckets are too.
+    let (first, last) = match (buckets.first(), buckets.last()) {
+        (Some(&(first, _)), Some(&(last, _))) => (first - 1, last - 1),
+        _ => return Ok(ExponentialBuckets::default()),
+    };
+    let mut bucket_counts = vec![0; (last - first) as usize + 1];
+    for (index, count) in buckets {
+        bucket_counts[(index - 1 - first) as usize] = count;
+    }
+    Ok(ExponentialBuckets {
+        offset: first as i32,
+        bucket_counts,
+    })
+}
+
+/// The magnitude of the largest bucket index that could hold a value with a
+/// bin: about `log2(1e128)`, at the largest schema.
+#[cfg(feature = "std")]
+const MAX_INDEX: i64 = 426 << NativeHistogram::MAX_SCHEMA;
+
+// === impl NativeHistogram ===
+
+impl NativeHistogram {
+    /// The smallest exponential schema, at which each bucket spans a factor of
+    /// 2^16.
+    pub const MIN_SCHEMA: i32 = -4;
+
+    /// The largest exponential schema, at which each bucket spans a factor of
+    /// 2^(2^-8).
+    pub const MAX_SCHEMA: i32 = 8;
+
+    /// The `zero_threshold` Prometheus uses by default, 2^-128.
+    pub const DEFAULT_ZERO_THRESHOLD: f64 = 2.938_735_877_055_719e-39;
+}
+
+// === impl NativeHistogramError ===
+
+impl fmt::Display for NativeHistogramError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidSchema(schema) => write!(
+                f,
+                "invalid native histogram schema {schema} (must be between {} and {})",
+                NativeHistogram::MIN_SCHEMA,
+                NativeHistogram::MAX_SCHEMA
+            ),
+            Self::SpanMismatch { spans, deltas } => write!(
+                f,
+                "native histogram spans cover {spans} buckets, but there are {deltas} deltas"
+            ),
+            Self::InvalidCount { index } => {
+                write!(f, "invalid native histogram bucket count at index {index}")
+            }
+            Self::InvalidIndex { index } => {
+                write!(f, "native histogram bucket index {index} is out of range")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for NativeHistogramError {}
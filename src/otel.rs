@@ -0,0 +1,445 @@
+//! Conversion to and from OpenTelemetry base-2 exponential histograms.
+//!
+//! An exponential histogram's buckets are determined by its `scale`: bucket
+//! `index` holds the values whose magnitude is in the interval
+//! `(base^index, base^(index + 1)]`, where `base = 2^(2^-scale)`. Positive and
+//! negative values are counted in separate bucket arrays, and zero in its own
+//! count. See
+//! <https://opentelemetry.io/docs/specs/otel/metrics/data-model/#exponentialhistogram>.
+#[cfg(feature = "std")]
+use crate::{bin::Bucket, Bin, BucketDistribution, Histogram};
+#[cfg(feature = "std")]
+use alloc::vec;
+use alloc::{string::String, vec::Vec};
+use core::fmt::{self, Write};
+
+/// A data point of an OpenTelemetry exponential histogram, shaped like OTLP's
+/// `ExponentialHistogramDataPoint` message.
+///
+/// The data point's attributes, exemplars and flags are not included.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExponentialHistogramDataPoint {
+    /// The start of the period the values were recorded in, in nanoseconds
+    /// since the Unix epoch.
+    pub start_time_unix_nano: u64,
+    /// The end of the period the values were recorded in, in nanoseconds since
+    /// the Unix epoch.
+    pub time_unix_nano: u64,
+    /// The number of values, which is `zero_count` plus the counts of all the
+    /// buckets.
+    pub count: u64,
+    /// The sum of the values, if known.
+    pub sum: Option<f64>,
+    /// The resolution of the buckets.
+    pub scale: i32,
+    /// The number of values whose magnitude is at most `zero_threshold`.
+    pub zero_count: u64,
+    /// The buckets for positive values.
+    pub positive: ExponentialBuckets,
+    /// The buckets for negative values, indexed by the values' magnitude.
+    pub negative: ExponentialBuckets,
+    /// The smallest value, if known.
+    pub min: Option<f64>,
+    /// The largest value, if known.
+    pub max: Option<f64>,
+    /// The largest magnitude of the values counted by `zero_count`.
+    pub zero_threshold: f64,
+}
+
+/// A contiguous range of the buckets of an exponential histogram, shaped like
+/// OTLP's `ExponentialHistogramDataPoint.Buckets` message.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct ExponentialBuckets {
+    /// The index of the first bucket in `bucket_counts`.
+    pub offset: i32,
+    /// The number of values in each bucket, starting at `offset`.
+    pub bucket_counts: Vec<u64>,
+}
+
+/// An error returned when converting an exponential histogram data point into
+/// a [`Histogram`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ExponentialHistogramError {
+    /// The data point's scale is outside the range OpenTelemetry allows.
+    InvalidScale(i32),
+}
+
+// the conversions need `f64` methods that are only available with `std`.
+#[cfg(feature = "std")]
+impl Histogram {
+    /// Returns this histogram as an OpenTelemetry exponential histogram data
+    /// point, at the smallest scale whose buckets are no wider, relative to the
+    /// values in them, than the narrowest of this histogram's bins.
+    ///
+    /// A bin's width relative to its values ranges from about 10% for the bins
+    /// starting with 1 (`[1.0, 1.1)`) to about 1% for the bins starting with 9
+    /// (`[9.9, 10)`), so the scale is between 3 (buckets about 9% wide) and 7
+    /// (about 0.5% wide). As OpenTelemetry SDKs do, the scale is lowered if
+    /// needed so that the positive and negative buckets each number at most
+    /// [`ExponentialHistogramDataPoint::DEFAULT_MAX_SIZE`]. See
+    /// [`Histogram::to_exponential_histogram_with_scale`] for how bins are
+    /// converted into buckets.
+    #[must_use]
+    pub fn to_exponential_histogram(&self) -> ExponentialHistogramDataPoint {
+        // the narrowest bins, relative to their values, have the most digits.
+        let widest_val = self
+            .bins
+            .iter()
+            .filter(|bucket| bucket.count > 0 && !bucket.bin.is_nan() && bucket.bin != Bin::ZERO)
+            .map(|bucket| bucket.bin.parts().0.unsigned_abs())
+            .max()
+            .unwrap_or(10);
+        let relative_width = 1.0 / f64::from(widest_val);
+        let scale = (ExponentialHistogramDataPoint::MIN_SCALE
+            ..ExponentialHistogramDataPoint::MAX_SCALE)
+            .find(|&scale| base(scale) - 1.0 <= relative_width)
+            .unwrap_or(ExponentialHistogramDataPoint::MAX_SCALE);
+        self.to_exponential_histogram_with_scale(scale)
+    }
+
+    /// Returns this histogram as an OpenTelemetry exponential histogram data
+    /// point with the given scale, or the largest smaller scale at which the
+    /// positive and negative buckets each number at most
+    /// [`ExponentialHistogramDataPoint::DEFAULT_MAX_SIZE`].
+    ///
+    /// See [`Histogram::to_exponential_histogram_with_max_size`] for details.
+    ///
+    /// # Panics
+    ///
+    /// If `scale` is outside the range OpenTelemetry allows, from
+    /// [`ExponentialHistogramDataPoint::MIN_SCALE`] to
+    /// [`ExponentialHistogramDataPoint::MAX_SCALE`].
+    #[must_use]
+    pub fn to_exponential_histogram_with_scale(&self, scale: i32) -> ExponentialHistogramDataPoint {
+        self.to_exponential_histogram_with_max_size(
+            scale,
+            ExponentialHistogramDataPoint::DEFAULT_MAX_SIZE,
+        )
+    }
+
+    /// Returns this histogram as an OpenTelemetry exponential histogram data
+    /// point with the largest scale, no larger than `max_scale`, at which the
+    /// positive and negative buckets each number at most `max_size`.
+    ///
+    /// Each bin's values are counted in the bucket containing its midpoint, and
+    /// the zero bin's in `zero_count`, with a `zero_threshold` of 0. The sum is
+    /// estimated from the bins' midpoints, as by [`Histogram::approx_sum`], and
+    /// the minimum and maximum are left unset. Values in the NaN bin can't be
+    /// represented, so they are not counted.
+    ///
+    /// # Panics
+    ///
+    /// If `max_scale` is outside the range OpenTelemetry allows, from
+    /// [`ExponentialHistogramDataPoint::MIN_SCALE`] to
+    /// [`ExponentialHistogramDataPoint::MAX_SCALE`], or `max_size` is less
+    /// than 2. Every histogram's buckets fit in 2 at the smallest scale.
+    #[must_use]
+    pub fn to_exponential_histogram_with_max_size(
+        &self,
+        max_scale: i32,
+        max_size: usize,
+    ) -> ExponentialHistogramDataPoint {
+        assert!(
+            (ExponentialHistogramDataPoint::MIN_SCALE..=ExponentialHistogramDataPoint::MAX_SCALE)
+                .contains(&max_scale),
+            "invalid exponential histogram scale {max_scale}"
+        );
+        assert!(
+            max_size >= 2,
+            "exponential histogram max size must be at least 2, not {max_size}"
+        );
+
+        // the buckets for each sign run from the one holding its smallest
+        // magnitude to the one holding its largest.
+        let (mut positive, mut negative) = (None, None);
+        for bucket in &self.bins {
+            if bucket.count == 0 || bucket.bin.is_nan() || bucket.bin == Bin::ZERO {
+                continue;
+            }
+            let midpoint = bucket.bin.midpoint();
+            let range = if midpoint > 0.0 {
+                &mut positive
+            } else {
+                &mut negative
+            };
+            let magnitude = midpoint.abs();
+            *range = match *range {
+                Some((min, max)) => Some((magnitude.min(min), magnitude.max(max))),
+                None => Some((magnitude, magnitude)),
+            };
+        }
+        let fits = |scale| {
+            [positive, negative].iter().flatten().all(|&(min, max)| {
+                let size = bucket_index(max, scale) - bucket_index(min, scale) + 1;
+                size as usize <= max_size
+            })
+        };
+        let scale = (ExponentialHistogramDataPoint::MIN_SCALE..=max_scale)
+            .rev()
+            .find(|&scale| fits(scale))
+            .unwrap_or(ExponentialHistogramDataPoint::MIN_SCALE);
+        self.to_exponential_histogram_with_threshold(scale, 0.0)
+    }
+
+    /// Returns this histogram as an exponential histogram data point with the
+    /// given scale, counting the bins whose midpoint's magnitude is at most
+    /// `zero_threshold` in `zero_count`.
+    pub(crate) fn to_exponential_histogram_with_threshold(
+        &self,
+        scale: i32,
+        zero_threshold: f64,
+    ) -> ExponentialHistogramDataPoint {
+        let mut point = ExponentialHistogramDataPoint {
+            scale,
+            sum: Some(0.0),
+            zero_threshold,
+            ..ExponentialHistogramDataPoint::default()
+        };
+        let (mut positive, mut negative) = (Vec::new(), Vec::new());
+        for bucket in self
+            .bins
+            .iter()
+            .filter(|bucket| bucket.count > 0 && !bucket.bin.is_nan())
+        {
+            let midpoint = bucket.bin.midpoint();
+            point.count = point.count.saturating_add(bucket.count);
+            point.sum = point.sum.map(|sum| sum + midpoint * bucket.count as f64);
+            if bucket.bin == Bin::ZERO || midpoint.abs() <= zero_threshold {
+                point.zero_count = point.zero_count.saturating_add(bucket.count);
+            } else if midpoint > 0.0 {
+                positive.push((bucket_index(midpoint, scale), bucket.count));
+            } else {
+                negative.push((bucket_index(-midpoint, scale), bucket.count));
+            }
+        }
+        point.positive = ExponentialBuckets::from_indices(positive);
+        point.negative = ExponentialBuckets::from_indices(negative);
+        point
+    }
+
+    /// Converts an OpenTelemetry exponential histogram data point into a
+    /// histogram.
+    ///
+    /// Each bucket's values are distributed over the bins its interval overlaps
+    /// according to `distribution`. At scale 4 and below, a bucket is more than
+    /// 4% wide, so it usually overlaps several bins. `zero_count` is recorded in
+    /// the zero bin, whatever the `zero_threshold`, and the data point's count,
+    /// sum, minimum and maximum are ignored.
+    pub fn from_exponential_histogram(
+        point: &ExponentialHistogramDataPoint,
+        distribution: BucketDistribution,
+    ) -> Result<Self, ExponentialHistogramError> {
+        let scale = point.scale;
+        if !(ExponentialHistogramDataPoint::MIN_SCALE..=ExponentialHistogramDataPoint::MAX_SCALE)
+            .contains(&scale)
+        {
+            return Err(ExponentialHistogramError::InvalidScale(scale));
+        }
+
+        let mut histogram = Histogram::new();
+        if point.zero_count > 0 {
+            histogram.insert_bucket(Bucket {
+                bin: Bin::ZERO,
+                count: point.zero_count,
+            });
+        }
+        for (buckets, is_negative) in [(&point.positive, false), (&point.negative, true)] {
+            for (index, count) in buckets.iter().filter(|&(_, count)| count > 0) {
+                let (lower, upper) = (bound(index, scale), bound(index + 1, scale));
+                let (lower, upper) = if is_negative {
+                    (-upper, -lower)
+                } else {
+                    (lower, upper)
+                };
+                histogram.insert_interval(lower, upper, count, distribution);
+            }
+        }
+        Ok(histogram)
+    }
+}
+
+/// Returns the base of the exponential histogram buckets at `scale`.
+#[cfg(feature = "std")]
+fn base(scale: i32) -> f64 {
+    2f64.powf(2f64.powi(-scale))
+}
+
+/// Returns the lower bound of the magnitude of the values in bucket `index`
+/// at `scale`, `base^index`.
+#[cfg(feature = "std")]
+fn bound(index: i64, scale: i32) -> f64 {
+    (index as f64 * 2f64.powi(-scale)).exp2()
+}
+
+/// Returns the index of the bucket containing the positive value `val` at
+/// `scale`.
+#[cfg(feature = "std")]
+fn bucket_index(val: f64, scale: i32) -> i64 {
+    // buckets include their upper bound, so exact powers of the base belong in
+    // the bucket below.
+    (val.log2() * 2f64.powi(scale)).ceil() as i64 - 1
+}
+
+// === impl ExponentialHistogramDataPoint ===
+
+impl ExponentialHistogramDataPoint {
+    /// The smallest scale OpenTelemetry allows, at which each bucket spans a
+    /// factor of 2^1024.
+    pub const MIN_SCALE: i32 = -10;
+
+    /// The largest scale OpenTelemetry allows, at which each bucket spans a
+    /// factor of 2^(2^-20).
+    pub const MAX_SCALE: i32 = 20;
+
+    /// The default largest number of positive or negative buckets, as in
+    /// OpenTelemetry SDKs' `max_size` setting.
+    pub const DEFAULT_MAX_SIZE: usize = 160;
+
+    /// Returns this data point in the OTLP JSON encoding.
+    ///
+    /// As in the rest of OTLP JSON, 64-bit integers are encoded as strings,
+    /// and non-finite floats as the strings `"NaN"`, `"Infinity"` and
+    /// `"-Infinity"`.
+    #[must_use]
+    pub fn to_otlp_json(&self) -> String {
+        let mut json = String::with_capacity(
+            192 + (self.positive.bucket_counts.len() + self.negative.bucket_counts.len()) * 4,
+        );
+        self.write_otlp_json(&mut json)
+            .expect("writing to a `String` never fails");
+        json
+    }
+
+    /// Writes this data point to `writer` in the OTLP JSON encoding.
+    ///
+    /// See [`ExponentialHistogramDataPoint::to_otlp_json`] for details.
+    pub fn write_otlp_json(&self, writer: &mut impl Write) -> fmt::Result {
+        write!(
+            writer,
+            "{{\"startTimeUnixNano\":\"{}\",\"timeUnixNano\":\"{}\",\"count\":\"{}\"",
+            self.start_time_unix_nano, self.time_unix_nano, self.count
+        )?;
+        if let Some(sum) = self.sum {
+            write!(writer, ",\"sum\":{}", JsonF64(sum))?;
+        }
+        write!(
+            writer,
+            ",\"scale\":{},\"zeroCount\":\"{}\",\"positive\":",
+            self.scale, self.zero_count
+        )?;
+        self.positive.write_otlp_json(writer)?;
+        writer.write_str(",\"negative\":")?;
+        self.negative.write_otlp_json(writer)?;
+        if let Some(min) = self.min {
+            write!(writer, ",\"min\":{}", JsonF64(min))?;
+        }
+        if let Some(max) = self.max {
+            write!(writer, ",\"max\":{}", JsonF64(max))?;
+        }
+        write!(
+            writer,
+            ",\"zeroThreshold\":{}}}",
+            JsonF64(self.zero_threshold)
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<&Histogram> for ExponentialHistogramDataPoint {
+    /// Converts a histogram as by [`Histogram::to_exponential_histogram`].
+    fn from(histogram: &Histogram) -> Self {
+        histogram.to_exponential_histogram()
+    }
+}
+
+#[cfg(feature = "std")]
+impl TryFrom<&ExponentialHistogramDataPoint> for Histogram {
+    type Error = ExponentialHistogramError;
+
+    /// Converts a data point as by [`Histogram::from_exponential_histogram`],
+    /// distributing each bucket's values uniformly.
+    fn try_from(point: &ExponentialHistogramDataPoint) -> Result<Self, Self::Error> {
+        Histogram::from_exponential_histogram(point, BucketDistribution::Uniform)
+    }
+}
+
+// === impl ExponentialBuckets ===
+
+impl ExponentialBuckets {
+    /// Returns each bucket's index and count.
+    pub fn iter(&self) -> impl Iterator<Item = (i64, u64)> + '_ {
+        let offset = i64::from(self.offset);
+        (offset..).zip(self.bucket_counts.iter().copied())
+    }
+
+    /// Returns the buckets with the given indices and counts, which may be
+    /// repeated and in any order.
+    #[cfg(feature = "std")]
+    fn from_indices(indices: Vec<(i64, u64)>) -> Self {
+        let (first, last) = match (
+            indices.iter().map(|&(index, _)| index).min(),
+            indices.iter().map(|&(index, _)| index).max(),
+        ) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return Self::default(),
+        };
+        // values too large or small to have a bin are in the NaN or zero bin,
+        // so indices fit in an `i32` at every scale.
+        let mut bucket_counts = vec![0u64; (last - first) as usize + 1];
+        for (index, count) in indices {
+            let bucket = &mut bucket_counts[(index - first) as usize];
+            *bucket = bucket.saturating_add(count);
+        }
+        Self {
+            offset: first as i32,
+            bucket_counts,
+        }
+    }
+
+    fn write_otlp_json(&self, writer: &mut impl Write) -> fmt::Result {
+        write!(writer, "{{\"offset\":{},\"bucketCounts\":[", self.offset)?;
+        for (i, count) in self.bucket_counts.iter().enumerate() {
+            if i > 0 {
+                writer.write_char(',')?;
+            }
+            write!(writer, "\"{count}\"")?;
+        }
+        writer.write_str("]}")
+    }
+}
+
+/// Formats an `f64` as a JSON number, or as a string if it isn't finite, as
+/// protobuf's JSON mapping does.
+struct JsonF64(f64);
+
+impl fmt::Display for JsonF64 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            val if val.is_nan() => f.write_str("\"NaN\""),
+            val if val == f64::INFINITY => f.write_str("\"Infinity\""),
+            val if val == f64::NEG_INFINITY => f.write_str("\"-Infinity\""),
+            // `Debug` uses exponential notation for very large and small values,
+            // which is valid JSON.
+            val => write!(f, "{val:?}"),
+        }
+    }
+}
+
+// === impl ExponentialHistogramError ===
+
+impl fmt::Display for ExponentialHistogramError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidScale(scale) => write!(
+                f,
+                "invalid exponential histogram scale {scale} (must be between {} and {})",
+                ExponentialHistogramDataPoint::MIN_SCALE,
+                ExponentialHistogramDataPoint::MAX_SCALE
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ExponentialHistogramError {}
@@ -0,0 +1,712 @@
+//! Conversion to and from the Prometheus text exposition format.
+//!
+//! A histogram is exposed as a "classic" Prometheus histogram: a family of
+//! cumulative `_bucket` series, one for each `le` ("less than or equal to")
+//! boundary, followed by `_sum` and `_count` series. See
+//! <https://prometheus.io/docs/instrumenting/exposition_formats/>.
+use crate::{bin::Bucket, Histogram};
+use alloc::{string::String, vec::Vec};
+use core::fmt::{self, Write};
+
+/// The `le` boundaries of the buckets a histogram is exposed with.
+#[derive(Debug, Clone, Copy, Default)]
+pub enum PrometheusBuckets<'a> {
+    /// A bucket at the upper bound of each of the histogram's bins.
+    #[default]
+    Bins,
+    /// A bucket at each of the given boundaries, which must be in strictly
+    /// increasing order. The `+Inf` bucket is added if it is not included.
+    ///
+    /// The count of each bucket is the number of values in bins that are
+    /// entirely less than or equal to its boundary, as returned by
+    /// [`Histogram::approx_count_below`].
+    Bounds(&'a [f64]),
+}
+
+/// A histogram formatted in the Prometheus text exposition format, returned
+/// by [`Histogram::prometheus`].
+#[derive(Debug, Clone, Copy)]
+#[must_use = "a PrometheusText does nothing unless formatted"]
+pub struct PrometheusText<'a> {
+    histogram: &'a Histogram,
+    name: &'a str,
+    labels: &'a [(&'a str, &'a str)],
+    buckets: PrometheusBuckets<'a>,
+}
+
+/// An error returned when a histogram can't be exposed to Prometheus with
+/// the given metric name, labels, or buckets.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum PrometheusError {
+    /// The metric name doesn't match `[a-zA-Z_:][a-zA-Z0-9_:]*`.
+    InvalidMetricName(String),
+    /// A label name doesn't match `[a-zA-Z_][a-zA-Z0-9_]*`, is reserved (it
+    /// starts with `__`), or is `le`, which is used for the bucket boundary.
+    InvalidLabelName(String),
+    /// The same label name was given more than once.
+    DuplicateLabel(String),
+    /// The `index`th bucket boundary is NaN, or not greater than the one
+    /// before it.
+    InvalidBound { index: usize, bound: f64 },
+}
+
+/// Formats an `f64` the way Prometheus clients do, like Go's
+/// `strconv.FormatFloat(f, 'g', -1, 64)`.
+struct GoFloat(f64);
+
+/// How the values counted by a Prometheus bucket are distributed over the
+/// bins its interval overlaps, when importing a Prometheus histogram.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum BucketDistribution {
+    /// Spread the values over the bins in proportion to how much of the
+    /// interval each bin covers, as if they were uniformly distributed within
+    /// it. This is the assumption Prometheus' `histogram_quantile` makes.
+    #[default]
+    Uniform,
+    /// Put all of the values in the bin containing the middle of the interval.
+    Midpoint,
+    /// Put all of the values in the highest bin the interval overlaps: the bin
+    /// containing the bucket's `le`, or ending at it if it's the lower bound of
+    /// a bin.
+    ///
+    /// Importing a histogram exposed with [`PrometheusBuckets::Bins`] this way
+    /// restores its bins exactly, other than the NaN bin.
+    UpperBound,
+}
+
+/// A histogram imported from a Prometheus histogram, returned by
+/// [`Histogram::from_prometheus`].
+#[derive(Debug, Clone)]
+pub struct PrometheusImport {
+    histogram: Histogram,
+    sum: Option<f64>,
+    count: Option<u64>,
+    precision_loss: Vec<PrecisionLoss>,
+}
+
+/// A Prometheus bucket interval whose values could not be placed in a single
+/// bin, so that their position within the interval had to be estimated.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PrecisionLoss {
+    lower: f64,
+    upper: f64,
+    count: u64,
+}
+
+/// An error returned when importing a histogram from the Prometheus or
+/// OpenMetrics text exposition format.
+///
+/// Line numbers start at 1.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum PrometheusParseError {
+    /// A sample of the histogram on `line` is malformed; `expected` describes
+    /// what was expected.
+    Syntax { line: usize, expected: &'static str },
+    /// The value of a sample on `line`, or its `le` label, is not a valid
+    /// number, or a bucket's or `_count`'s value is not a whole number.
+    InvalidValue { line: usize, value: String },
+    /// The `_bucket` sample on `line` has no `le` label.
+    MissingLe { line: usize },
+    /// A series has two buckets with the same `le`.
+    DuplicateBucket { le: f64 },
+    /// The bucket with `le` counts fewer values than the bucket before it.
+    NotCumulative { le: f64 },
+    /// A series has no `+Inf` bucket.
+    MissingInfBucket,
+    /// A series' `_count` doesn't match its `+Inf` bucket.
+    CountMismatch { buckets: u64, count: u64 },
+    /// The text has no buckets for the histogram.
+    NotFound,
+}
+
+/// The samples of one series of a Prometheus histogram: the `_bucket`,
+/// `_sum` and `_count` samples with the same labels (other than `le`).
+#[derive(Default)]
+struct Series {
+    labels: Vec<(String, String)>,
+    buckets: Vec<(f64, u64)>,
+    sum: Option<f64>,
+    count: Option<u64>,
+}
+
+/// The labels and value of a sample line of the text exposition format.
+struct Sample {
+    labels: Vec<(String, String)>,
+    value: String,
+}
+
+impl Histogram {
+    /// Returns this histogram as a Prometheus histogram named `name`, with the
+    /// given labels, in the text exposition format.
+    ///
+    /// The output starts with a `# TYPE` line, followed by the cumulative
+    /// `_bucket` series for each of the `buckets`, ending with the `+Inf`
+    /// bucket, and the `_sum` and `_count` series. Values in the NaN bin are
+    /// only counted by the `+Inf` bucket and `_count`, and make `_sum` NaN,
+    /// just as Prometheus clients do for NaN observations.
+    ///
+    /// Since positive bins exclude their upper bound, when exposing the bins'
+    /// own boundaries, a positive value equal to a bin's upper bound is counted
+    /// in the next bucket rather than the one with that `le`.
+    pub fn prometheus<'a>(
+        &'a self,
+        name: &'a str,
+        labels: &'a [(&'a str, &'a str)],
+        buckets: PrometheusBuckets<'a>,
+    ) -> Result<PrometheusText<'a>, PrometheusError> {
+        if !is_metric_name(name) {
+            return Err(PrometheusError::InvalidMetricName(name.into()));
+        }
+        for (i, &(label, _)) in labels.iter().enumerate() {
+            if !is_label_name(label) || label.starts_with("__") || label == "le" {
+                return Err(PrometheusError::InvalidLabelName(label.into()));
+            }
+            if labels[..i].iter().any(|&(other, _)| other == label) {
+                return Err(PrometheusError::DuplicateLabel(label.into()));
+            }
+        }
+        if let PrometheusBuckets::Bounds(bounds) = buckets {
+            for (index, &bound) in bounds.iter().enumerate() {
+                if bound.is_nan() || index > 0 && bound <= bounds[index - 1] {
+                    return Err(PrometheusError::InvalidBound { index, bound });
+                }
+            }
+        }
+
+        Ok(PrometheusText {
+            histogram: self,
+            name,
+            labels,
+            buckets,
+        })
+    }
+
+    /// Imports the Prometheus histogram `name` from `text`, in the Prometheus
+    /// or OpenMetrics text exposition format.
+    ///
+    /// Every series of the histogram whose labels include all of `labels` is
+    /// imported, and merged into a single histogram. Each bucket's interval,
+    /// from the previous bucket's `le` (exclusive) to its own (inclusive),
+    /// usually spans many bins, so its values are spread across them according
+    /// to `distribution`, and the interval is reported in
+    /// [`PrometheusImport::precision_loss`]. As Prometheus' `histogram_quantile`
+    /// does, the first bucket is assumed to start at zero if its `le` is
+    /// positive, and the values in the `+Inf` bucket are assumed to equal the
+    /// `le` before it.
+    ///
+    /// Lines other than the histogram's `_bucket`, `_sum` and `_count` samples
+    /// are ignored, as are sample timestamps and exemplars.
+    pub fn from_prometheus(
+        text: &str,
+        name: &str,
+        labels: &[(&str, &str)],
+        distribution: BucketDistribution,
+    ) -> Result<PrometheusImport, PrometheusParseError> {
+        let mut series = Vec::<Series>::new();
+        for (line, sample) in text.lines().enumerate() {
+            let line = line + 1;
+            let sample = sample.trim();
+            if sample.is_empty() || sample.starts_with('#') {
+                continue;
+            }
+            let name_len = sample
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == ':'))
+                .unwrap_or(sample.len());
+            let suffix = match sample[..name_len].strip_prefix(name) {
+                Some(suffix @ ("_bucket" | "_sum" | "_count")) => suffix,
+                _ => continue,
+            };
+            let sample = Sample::parse(&sample[name_len..], line)?;
+
+            if !labels
+                .iter()
+                .all(|&(label, value)| sample.labels.iter().any(|(l, v)| *l == label && v == value))
+            {
+                continue;
+            }
+            let mut le = None;
+            let mut series_labels = Vec::with_capacity(sample.labels.len());
+            for (label, value) in sample.labels {
+                if label == "le" {
+                    match parse_float(&value, line)? {
+                        val if val.is_nan() => {
+                            return Err(PrometheusParseError::InvalidValue { line, value })
+                        }
+                        val => le = Some(val),
+                    }
+                } else {
+                    series_labels.push((label, value));
+                }
+            }
+            series_labels.sort();
+            let idx = match series.iter().position(|s| s.labels == series_labels) {
+                Some(idx) => idx,
+                None => {
+                    series.push(Series {
+                        labels: series_labels,
+                        ..Series::default()
+                    });
+                    series.len() - 1
+                }
+            };
+            let series = &mut series[idx];
+
+            match suffix {
+                "_bucket" => {
+                    let le = le.ok_or(PrometheusParseError::MissingLe { line })?;
+                    series.buckets.push((le, parse_count(&sample.value, line)?));
+                }
+                "_sum" => series.sum = Some(parse_float(&sample.value, line)?),
+                _ => series.count = Some(parse_count(&sample.value, line)?),
+            }
+        }
+
+        if series.iter().all(|series| series.buckets.is_empty()) {
+            return Err(PrometheusParseError::NotFound);
+        }
+        let mut import = PrometheusImport {
+            histogram: Histogram::new(),
+            sum: Some(0.0),
+            count: Some(0),
+            precision_loss: Vec::new(),
+        };
+        for series in series {
+            series.import(distribution, &mut import)?;
+        }
+        Ok(import)
+    }
+}
+
+fn is_metric_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .map_or(false, |c| c.is_ascii_alphabetic() || c == '_' || c == ':')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':')
+}
+
+fn is_label_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .map_or(false, |c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+// === impl PrometheusText ===
+
+impl PrometheusText<'_> {
+    /// Writes the series name `{name}{suffix}` and its labels, including `le`
+    /// if it's given.
+    fn series(&self, f: &mut fmt::Formatter<'_>, suffix: &str, le: Option<f64>) -> fmt::Result {
+        write!(f, "{}{suffix}", self.name)?;
+        if self.labels.is_empty() && le.is_none() {
+            return Ok(());
+        }
+        f.write_char('{')?;
+        for (i, (label, value)) in self.labels.iter().enumerate() {
+            if i > 0 {
+                f.write_char(',')?;
+            }
+            write!(f, "{label}=\"")?;
+            for c in value.chars() {
+                match c {
+                    '\\' => f.write_str(r"\\")?,
+                    '"' => f.write_str(r#"\""#)?,
+                    '\n' => f.write_str(r"\n")?,
+                    c => f.write_char(c)?,
+                }
+            }
+            f.write_char('"')?;
+        }
+        if let Some(le) = le {
+            if !self.labels.is_empty() {
+                f.write_char(',')?;
+            }
+            write!(f, "le=\"{}\"", GoFloat(le))?;
+        }
+        f.write_char('}')
+    }
+}
+
+impl fmt::Display for PrometheusText<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bins = &self.histogram.bins;
+        writeln!(f, "# TYPE {} histogram", self.name)?;
+
+        match self.buckets {
+            PrometheusBuckets::Bins => {
+                let mut cumulative = 0u64;
+                for &Bucket { bin, count } in bins.iter().filter(|bucket| !bucket.bin.is_nan()) {
+                    cumulative = cumulative.saturating_add(count);
+                    self.series(f, "_bucket", Some(bin.upper_bound()))?;
+                    writeln!(f, " {cumulative}")?;
+                }
+            }
+            PrometheusBuckets::Bounds(bounds) => {
+                for &bound in bounds.iter().filter(|&&bound| bound != f64::INFINITY) {
+                    self.series(f, "_bucket", Some(bound))?;
+                    writeln!(f, " {}", self.histogram.approx_count_below(bound))?;
+                }
+            }
+        }
+
+        let count = bins
+            .iter()
+            .fold(0u64, |count, bucket| count.saturating_add(bucket.count));
+        self.series(f, "_bucket", Some(f64::INFINITY))?;
+        writeln!(f, " {count}")?;
+        self.series(f, "_sum", None)?;
+        writeln!(f, " {}", GoFloat(self.histogram.approx_sum()))?;
+        self.series(f, "_count", None)?;
+        writeln!(f, " {count}")
+    }
+}
+
+// === impl GoFloat ===
+
+impl fmt::Display for GoFloat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let val = self.0;
+        if val.is_nan() {
+            return f.write_str("NaN");
+        } else if val.is_infinite() {
+            return f.write_str(if val > 0.0 { "+Inf" } else { "-Inf" });
+        }
+
+        // Go uses the shortest representation that round-trips, in
+        // exponential notation if the exponent is less than -4 or at least 6.
+        let exp_str = alloc::format!("{val:e}");
+        let (mantissa, exp) = exp_str
+            .split_once('e')
+            .expect("`LowerExp` always includes an exponent");
+        let exp: i32 = exp.parse().expect("the exponent is an integer");
+        if (-4..6).contains(&exp) {
+            write!(f, "{val}")
+        } else {
+            let sign = if exp < 0 { '-' } else { '+' };
+            write!(f, "{mantissa}e{sign}{:02}", exp.unsigned_abs())
+        }
+    }
+}
+
+// === impl PrometheusError ===
+
+impl fmt::Display for PrometheusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidMetricName(name) => write!(f, "invalid Prometheus metric name `{name}`"),
+            Self::InvalidLabelName(label) => write!(f, "invalid Prometheus label name `{label}`"),
+            Self::DuplicateLabel(label) => write!(f, "duplicate Prometheus label `{label}`"),
+            Self::InvalidBound { index, bound } => write!(
+                f,
+                "invalid bucket boundary {bound} at index {index} (boundaries must be \
+                 increasing and not NaN)"
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PrometheusError {}
+
+// === impl PrometheusImport ===
+
+impl PrometheusImport {
+    /// Returns the imported histogram.
+    #[must_use]
+    pub fn histogram(&self) -> &Histogram {
+        &self.histogram
+    }
+
+    /// Returns the imported histogram, discarding everything else.
+    #[must_use]
+    pub fn into_histogram(self) -> Histogram {
+        self.histogram
+    }
+
+    /// Returns the sum of the `_sum` samples, or `None` if a series had none.
+    ///
+    /// Unlike [`Histogram::approx_sum`], this is the exact sum of the values
+    /// the imported histogram was built from.
+    #[must_use]
+    pub fn sum(&self) -> Option<f64> {
+        self.sum
+    }
+
+    /// Returns the sum of the `_count` samples, or `None` if a series had none.
+    #[must_use]
+    pub fn count(&self) -> Option<u64> {
+        self.count
+    }
+
+    /// Returns the bucket intervals whose values were spread over, or placed
+    /// in one of, several bins, in the order they were imported.
+    #[must_use]
+    pub fn precision_loss(&self) -> &[PrecisionLoss] {
+        &self.precision_loss
+    }
+}
+
+// === impl PrecisionLoss ===
+
+impl PrecisionLoss {
+    /// Returns the (exclusive) lower bound of the bucket's interval: the `le`
+    /// of the bucket before it, or negative infinity for the first bucket.
+    #[must_use]
+    pub fn lower(&self) -> f64 {
+        self.lower
+    }
+
+    /// Returns the (inclusive) upper bound of the bucket's interval, its `le`.
+    #[must_use]
+    pub fn upper(&self) -> f64 {
+        self.upper
+    }
+
+    /// Returns the number of values in the interval.
+    #[must_use]
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+}
+
+// === impl Series ===
+
+impl Series {
+    /// Converts this series' cumulative buckets into bins, and adds them and
+    /// its sum and count to `import`.
+    fn import(
+        mut self,
+        distribution: BucketDistribution,
+        import: &mut PrometheusImport,
+    ) -> Result<(), PrometheusParseError> {
+        self.buckets.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+        if let Some(w) = self.buckets.windows(2).find(|w| w[0].0 == w[1].0) {
+            return Err(PrometheusParseError::DuplicateBucket { le: w[0].0 });
+        }
+        let total = match self.buckets.last() {
+            Some(&(le, count)) if le == f64::INFINITY => count,
+            _ => return Err(PrometheusParseError::MissingInfBucket),
+        };
+        if let Some(count) = self.count.filter(|&count| count != total) {
+            return Err(PrometheusParseError::CountMismatch {
+                buckets: total,
+                count,
+            });
+        }
+
+        let (mut lower, mut below) = (f64::NEG_INFINITY, 0);
+        for (upper, cumulative) in self.buckets {
+            let count = cumulative
+                .checked_sub(below)
+                .ok_or(PrometheusParseError::NotCumulative { le: upper })?;
+            if count > 0 && !distribute(lower, upper, count, distribution, &mut import.histogram) {
+                import.precision_loss.push(PrecisionLoss {
+                    lower,
+                    upper,
+                    count,
+                });
+            }
+            (lower, below) = (upper, cumulative);
+        }
+
+        import.sum = import.sum.zip(self.sum).map(|(a, b)| a + b);
+        import.count = import
+            .count
+            .zip(self.count)
+            .map(|(a, b)| a.saturating_add(b));
+        Ok(())
+    }
+}
+
+/// Adds `count` values in the interval `(lower, upper]` to `histogram`,
+/// returning `true` if they all belong in a single bin.
+fn distribute(
+    lower: f64,
+    upper: f64,
+    count: u64,
+    distribution: BucketDistribution,
+    histogram: &mut Histogram,
+) -> bool {
+    // unbounded intervals are bounded the way `histogram_quantile` does.
+    let bounded = lower.is_finite() && upper.is_finite();
+    let (lower, upper) = match (lower.is_finite(), upper.is_finite()) {
+        (true, true) => (lower, upper),
+        (false, true) if upper > 0.0 => (0.0, upper),
+        (false, true) => (upper, upper),
+        (true, false) => (lower, lower),
+        (false, false) => (0.0, 0.0),
+    };
+
+    let bins = histogram.insert_interval(lower, upper, count, distribution);
+    bounded && bins <= 1
+}
+
+fn parse_float(value: &str, line: usize) -> Result<f64, PrometheusParseError> {
+    value
+        .parse()
+        .map_err(|_| PrometheusParseError::InvalidValue {
+            line,
+            value: value.into(),
+        })
+}
+
+fn parse_count(value: &str, line: usize) -> Result<u64, PrometheusParseError> {
+    match parse_float(value, line)? {
+        // `f64::fract` needs `std`, but a count that's an integer survives
+        // the round trip through `u64`.
+        count if count >= 0.0 && count < u64::MAX as f64 && count as u64 as f64 == count => {
+            Ok(count as u64)
+        }
+        _ => Err(PrometheusParseError::InvalidValue {
+            line,
+            value: value.into(),
+        }),
+    }
+}
+
+// === impl Sample ===
+
+impl Sample {
+    /// Parses the rest of a sample line after the metric name, such as
+    /// `{label="value"} 1.5 1700000000`.
+    fn parse(mut rest: &str, line: usize) -> Result<Self, PrometheusParseError> {
+        let syntax = |expected| PrometheusParseError::Syntax { line, expected };
+
+        let mut labels = Vec::new();
+        if let Some(label_set) = rest.strip_prefix('{') {
+            rest = label_set.trim_start();
+            // a trailing comma is allowed.
+            while !rest.starts_with('}') {
+                let (label, value) = rest.split_once('=').ok_or(syntax("`=`"))?;
+                let label = label.trim_end();
+                if !is_label_name(label) {
+                    return Err(syntax("a label name"));
+                }
+                let mut chars = value
+                    .trim_start()
+                    .strip_prefix('"')
+                    .ok_or(syntax("`\"`"))?
+                    .chars();
+                let mut unescaped = String::new();
+                loop {
+                    match chars.next() {
+                        Some('\\') => match chars.next() {
+                            Some('\\') => unescaped.push('\\'),
+                            Some('"') => unescaped.push('"'),
+                            Some('n') => unescaped.push('\n'),
+                            _ => return Err(syntax("an escape sequence")),
+                        },
+                        Some('"') => break,
+                        Some(c) => unescaped.push(c),
+                        None => return Err(syntax("`\"`")),
+                    }
+                }
+                labels.push((label.into(), unescaped));
+                rest = chars.as_str().trim_start();
+                if let Some(next) = rest.strip_prefix(',') {
+                    rest = next.trim_start();
+                } else if !rest.starts_with('}') {
+                    return Err(syntax("`,` or `}`"));
+                }
+            }
+            rest = &rest[1..];
+        }
+
+        if !rest.starts_with([' ', '\t']) {
+            return Err(syntax("a space"));
+        }
+        let value = rest
+            .split_ascii_whitespace()
+            .next()
+            .ok_or(syntax("a value"))?
+            .into();
+        Ok(Self { labels, value })
+    }
+}
+
+// === impl PrometheusParseError ===
+
+impl fmt::Display for PrometheusParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Syntax { line, expected } => {
+                write!(
+                    f,
+                    "invalid Prometheus sample on line {line}: expected {expected}"
+                )
+            }
+            Self::InvalidValue { line, value } => {
+                write!(
+                    f,
+                    "invalid Prometheus sample value `{value}` on line {line}"
+                )
+            }
+            Self::MissingLe { line } => {
+                write!(f, "Prometheus bucket on line {line} has no `le` label")
+            }
+            Self::DuplicateBucket { le } => write!(f, "duplicate Prometheus bucket le=\"{le}\""),
+            Self::NotCumulative { le } => write!(
+                f,
+                "Prometheus bucket le=\"{le}\" has a lower count than the bucket before it"
+            ),
+            Self::MissingInfBucket => f.write_str("Prometheus histogram has no `+Inf` bucket"),
+            Self::CountMismatch { buckets, count } => write!(
+                f,
+                "Prometheus histogram `_count` {count} doesn't match its `+Inf` bucket {buckets}"
+            ),
+            Self::NotFound => f.write_str("no Prometheus histogram buckets found"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PrometheusParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use proptest::{num::f64::ANY, prop_assert_eq, prop_assume, proptest};
+
+    proptest! {
+        #[test]
+        fn go_float_roundtrips(val in ANY) {
+            prop_assume!(val.is_finite());
+            let formatted = GoFloat(val).to_string();
+            let parsed: f64 = formatted.parse().unwrap();
+            prop_assert_eq!(parsed.to_bits(), val.to_bits(), "{}", formatted)
+        }
+    }
+
+    #[test]
+    fn go_float() {
+        // the output of Go's `strconv.FormatFloat(val, 'g', -1, 64)`.
+        for (val, expected) in [
+            (0.0, "0"),
+            (-0.0, "-0"),
+            (0.1, "0.1"),
+            (123_456.0, "123456"),
+            (999_999.999_999_999_9, "999999.9999999999"),
+            (1e6, "1e+06"),
+            (12_345_678.0, "1.2345678e+07"),
+            (1e21, "1e+21"),
+            (-2.5e21, "-2.5e+21"),
+            (1e-4, "0.0001"),
+            (9.9e-5, "9.9e-05"),
+            (1e-5, "1e-05"),
+            (1.5e-300, "1.5e-300"),
+            (5e-324, "5e-324"),
+            (f64::MAX, "1.7976931348623157e+308"),
+            (f64::INFINITY, "+Inf"),
+            (f64::NEG_INFINITY, "-Inf"),
+            (f64::NAN, "NaN"),
+        ] {
+            assert_eq!(GoFloat(val).to_string(), expected, "{val:?}");
+        }
+    }
+}
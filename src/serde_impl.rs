@@ -0,0 +1,132 @@
+//! `serde` support for [`Histogram`].
+//!
+//! Human-readable formats represent a histogram as a list of bin strings in
+//! the same form as the Go library's `DecStrings` (e.g.
+//! `["H[0.0e+00]=1", "H[1.2e-01]=2"]`), which can be parsed by
+//! [`Histogram::from_strs`]. Binary formats use a more compact list of
+//! `(value, exponent, count)` tuples.
+use crate::{
+    bin::{Bin, Bucket},
+    Histogram,
+};
+use core::fmt;
+use serde::{
+    de::{self, Deserialize, Deserializer, SeqAccess, Visitor},
+    ser::{Serialize, SerializeSeq, Serializer},
+};
+
+/// Serializes a bucket as a bin string.
+struct BinStr<'a>(&'a Bucket);
+
+/// Deserializes a bin string, using the same validation as `from_strs`.
+struct ParsedBinStr(Bin, i64);
+
+/// Deserializes a `(value, exponent, count)` tuple.
+struct BinTuple(Bucket);
+
+struct HistogramVisitor {
+    human_readable: bool,
+}
+
+impl Serialize for Histogram {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let human_readable = serializer.is_human_readable();
+        let mut seq = serializer.serialize_seq(Some(self.bins.len()))?;
+        for bucket in &self.bins {
+            if human_readable {
+                seq.serialize_element(&BinStr(bucket))?;
+            } else {
+                let (val, exp) = bucket.bin.parts();
+                seq.serialize_element(&(val, exp, bucket.count))?;
+            }
+        }
+        seq.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Histogram {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let human_readable = deserializer.is_human_readable();
+        deserializer.deserialize_seq(HistogramVisitor { human_readable })
+    }
+}
+
+// === impl HistogramVisitor ===
+
+impl<'de> Visitor<'de> for HistogramVisitor {
+    type Value = Histogram;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.human_readable {
+            f.write_str("a list of histogram bin strings")
+        } else {
+            f.write_str("a list of (value, exponent, count) histogram bins")
+        }
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        // as in `from_strs`, the input may contain the same bin multiple
+        // times, so `insert` rather than pushing to coalesce them.
+        let mut histogram = Histogram::with_capacity(seq.size_hint().unwrap_or(0));
+        if self.human_readable {
+            while let Some(ParsedBinStr(bin, count)) = seq.next_element()? {
+                histogram.insert(bin, count);
+            }
+        } else {
+            while let Some(BinTuple(bucket)) = seq.next_element()? {
+                histogram.insert_bucket(bucket);
+            }
+        }
+        Ok(histogram)
+    }
+}
+
+// === impl BinStr ===
+
+impl fmt::Display for BinStr<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt_canonical(f)
+    }
+}
+
+impl Serialize for BinStr<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+// === impl ParsedBinStr ===
+
+impl<'de> Deserialize<'de> for ParsedBinStr {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct StrVisitor;
+
+        impl<'de> Visitor<'de> for StrVisitor {
+            type Value = ParsedBinStr;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a histogram bin string, such as `H[1.2e-01]=2`")
+            }
+
+            fn visit_str<E: de::Error>(self, s: &str) -> Result<Self::Value, E> {
+                let (bin, count) = Bin::from_str(s)
+                    .map_err(|(error, _)| E::custom(format_args!("invalid bin `{s}`: {error}")))?;
+                Ok(ParsedBinStr(bin, count))
+            }
+        }
+
+        deserializer.deserialize_str(StrVisitor)
+    }
+}
+
+// === impl BinTuple ===
+
+impl<'de> Deserialize<'de> for BinTuple {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (val, exp, count) = <(i8, i8, u64)>::deserialize(deserializer)?;
+        let bin = Bin::from_parts(val, exp).ok_or_else(|| {
+            de::Error::custom(format_args!("invalid bin (val: {val}, exp: {exp})"))
+        })?;
+        Ok(BinTuple(Bucket { bin, count }))
+    }
+}
@@ -0,0 +1,452 @@
+//! Conversion to and from DDSketch and t-digest sketches.
+//!
+//! A [DDSketch](https://arxiv.org/abs/1908.10693) with relative accuracy `α`
+//! counts values in logarithmic buckets: bucket `index` holds the values whose
+//! magnitude is in the interval `(γ^(index - 1), γ^index]`, where
+//! `γ = (1 + α) / (1 - α)`, shifted by the sketch's index offset. Positive and
+//! negative values are counted in separate stores, and zero in its own count.
+//!
+//! These are the buckets of the `sketches-ddsketch` crate. sketches-go and
+//! sketches-java instead put a value `v` in bucket
+//! `floor(log_γ(v)) + indexOffset`, the interval
+//! `[γ^(index - indexOffset), γ^(index - indexOffset + 1))`; their sketches
+//! convert with an `index_offset` of their `indexOffset - 1`, except that values
+//! on a bucket's bound are counted in the bucket below.
+//!
+//! A [t-digest](https://arxiv.org/abs/1902.04023) summarizes values as a list
+//! of centroids, each the mean of a run of values that are adjacent in sorted
+//! order, and the number of values in the run.
+use crate::{bin::Bucket, Bin, BucketDistribution, Histogram};
+use alloc::{collections::BTreeMap, vec::Vec};
+use core::fmt;
+
+/// A DDSketch with the logarithmic index mapping, shaped like the `DDSketch`
+/// message of the DDSketch protocol buffers, with integer counts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DDSketch {
+    /// The ratio between the bounds of each bucket, greater than 1.
+    pub gamma: f64,
+    /// The offset added to every bucket index: bucket `index` holds the values
+    /// whose magnitude is in `(γ^(index - index_offset - 1), γ^(index - index_offset)]`.
+    pub index_offset: i32,
+    /// The number of zero values.
+    pub zero_count: u64,
+    /// The number of positive values in each non-empty bucket, by index.
+    pub positive: BTreeMap<i32, u64>,
+    /// The number of negative values in each non-empty bucket, by the index
+    /// of the values' magnitude.
+    pub negative: BTreeMap<i32, u64>,
+}
+
+/// A t-digest, as the list of its centroids and the range of its values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TDigest {
+    /// The centroids, in ascending order of their means.
+    pub centroids: Vec<Centroid>,
+    /// The smallest value, or NaN if it isn't known.
+    pub min: f64,
+    /// The largest value, or NaN if it isn't known.
+    pub max: f64,
+}
+
+/// A centroid of a [`TDigest`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Centroid {
+    /// The mean of the centroid's values.
+    pub mean: f64,
+    /// The number of values.
+    pub weight: u64,
+}
+
+/// An error returned when converting a [`DDSketch`] into a [`Histogram`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum DDSketchError {
+    /// `gamma` is not a finite number greater than 1.
+    InvalidGamma(f64),
+}
+
+/// An error returned when converting a [`TDigest`] into a [`Histogram`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum TDigestError {
+    /// The mean of the centroid at `index` is not finite, or is less than the
+    /// mean of the centroid before it.
+    InvalidMean { index: usize },
+    /// The minimum or maximum is infinite, or doesn't contain the centroids'
+    /// means.
+    InvalidRange { min: f64, max: f64 },
+}
+
+impl Histogram {
+    /// Returns this histogram as a DDSketch with the given relative accuracy.
+    ///
+    /// Each bin's values are counted in the bucket containing its midpoint,
+    /// and the zero bin's in `zero_count`. The sketch's index offset is 0.
+    /// DDSketch has no NaN values, so values in the NaN bin are not included.
+    ///
+    /// # Panics
+    ///
+    /// If `relative_accuracy` is not at least
+    /// [`DDSketch::MIN_RELATIVE_ACCURACY`] and less than 1.
+    #[cfg(feature = "std")]
+    #[must_use]
+    pub fn to_ddsketch(&self, relative_accuracy: f64) -> DDSketch {
+        assert!(
+            (DDSketch::MIN_RELATIVE_ACCURACY..1.0).contains(&relative_accuracy),
+            "invalid DDSketch relative accuracy {relative_accuracy}"
+        );
+
+        let gamma = (1.0 + relative_accuracy) / (1.0 - relative_accuracy);
+        let mut sketch = DDSketch {
+            gamma,
+            index_offset: 0,
+            zero_count: 0,
+            positive: BTreeMap::new(),
+            negative: BTreeMap::new(),
+        };
+        for bucket in self.bins.iter().filter(|bucket| !bucket.bin.is_nan()) {
+            let midpoint = bucket.bin.midpoint();
+            let count = if bucket.bin == Bin::ZERO {
+                &mut sketch.zero_count
+            } else if midpoint > 0.0 {
+                sketch.positive.entry(key(midpoint, gamma)).or_default()
+            } else {
+                sketch.negative.entry(key(-midpoint, gamma)).or_default()
+            };
+            *count = count.saturating_add(bucket.count);
+        }
+        sketch
+    }
+
+    /// Converts a DDSketch into a histogram.
+    ///
+    /// Each bucket's values are distributed over the bins its interval overlaps
+    /// according to `distribution`. With a relative accuracy of 0.5% or more, a
+    /// bucket is at least 1% wide, so it often overlaps more than one bin.
+    #[cfg(feature = "std")]
+    pub fn from_ddsketch(
+        sketch: &DDSketch,
+        distribution: BucketDistribution,
+    ) -> Result<Self, DDSketchError> {
+        let gamma = sketch.gamma;
+        if !(gamma.is_finite() && gamma > 1.0) {
+            return Err(DDSketchError::InvalidGamma(gamma));
+        }
+
+        let mut histogram = Histogram::new();
+        if sketch.zero_count > 0 {
+            histogram.insert_bucket(Bucket {
+                bin: Bin::ZERO,
+                count: sketch.zero_count,
+            });
+        }
+        for (store, is_negative) in [(&sketch.positive, false), (&sketch.negative, true)] {
+            for (&index, &count) in store.iter().filter(|&(_, &count)| count > 0) {
+                let (lower, upper) = bounds(index, sketch.index_offset, gamma);
+                let (lower, upper) = if is_negative {
+                    (-upper, -lower)
+                } else {
+                    (lower, upper)
+                };
+                histogram.insert_interval(lower, upper, count, distribution);
+            }
+        }
+        Ok(histogram)
+    }
+
+    /// Returns this histogram as a t-digest, with a centroid at the midpoint
+    /// of each bin.
+    ///
+    /// The digest's minimum and maximum are the lower bound of the first bin
+    /// and the upper bound of the last. A t-digest has no NaN values, so values
+    /// in the NaN bin are not included. The digest is not compressed: it has a
+    /// centroid for every bin, however many centroids a t-digest would usually
+    /// keep.
+    #[must_use]
+    pub fn to_tdigest(&self) -> TDigest {
+        let buckets = self.bins.iter().filter(|bucket| !bucket.bin.is_nan());
+        let centroids: Vec<_> = buckets
+            .clone()
+            .map(|bucket| Centroid {
+                mean: bucket.bin.midpoint(),
+                weight: bucket.count,
+            })
+            .collect();
+        let (min, max) = match (buckets.clone().next(), buckets.last()) {
+            (Some(first), Some(last)) => (first.bin.lower_bound(), last.bin.upper_bound()),
+            _ => (f64::NAN, f64::NAN),
+        };
+        TDigest {
+            centroids,
+            min,
+            max,
+        }
+    }
+
+    /// Converts a t-digest into a histogram.
+    ///
+    /// A centroid of one value is recorded at its mean. The values of a larger
+    /// centroid are assumed to lie in an interval centred on its mean and
+    /// reaching no further than halfway to the neighbouring centroids' means,
+    /// or past the digest's minimum and maximum, and are distributed over the
+    /// bins that interval overlaps according to `distribution`. If the minimum
+    /// or maximum is NaN, the first or last centroid reaches no further than
+    /// its mean on that side.
+    pub fn from_tdigest(
+        digest: &TDigest,
+        distribution: BucketDistribution,
+    ) -> Result<Self, TDigestError> {
+        let centroids = &digest.centroids;
+        for (index, centroid) in centroids.iter().enumerate() {
+            let sorted = index == 0 || centroids[index - 1].mean <= centroid.mean;
+            if !(centroid.mean.is_finite() && sorted) {
+                return Err(TDigestError::InvalidMean { index });
+            }
+        }
+        let (first, last) = match (centroids.first(), centroids.last()) {
+            (Some(first), Some(last)) => (first.mean, last.mean),
+            _ => (f64::NAN, f64::NAN),
+        };
+        let (min, max) = (digest.min, digest.max);
+        // NaN compares false, so an unknown minimum or maximum is valid.
+        if min.is_infinite() || max.is_infinite() || min > first || max < last {
+            return Err(TDigestError::InvalidRange { min, max });
+        }
+        let min = if min.is_nan() { first } else { min };
+        let max = if max.is_nan() { last } else { max };
+
+        let mut histogram = Histogram::new();
+        for (index, centroid) in centroids.iter().enumerate() {
+            if centroid.weight == 0 {
+                continue;
+            }
+            let mean = centroid.mean;
+            if centroid.weight == 1 {
+                histogram.insert_bucket(Bucket {
+                    bin: Bin::for_value(mean),
+                    count: 1,
+                });
+                continue;
+            }
+            let lower = match index {
+                0 => min,
+                _ => mean - (mean - centroids[index - 1].mean) / 2.0,
+            };
+            let upper = match centroids.get(index + 1) {
+                None => max,
+                Some(next) => mean + (next.mean - mean) / 2.0,
+            };
+            let reach = (mean - lower).min(upper - mean);
+            histogram.insert_interval(mean - reach, mean + reach, centroid.weight, distribution);
+        }
+        Ok(histogram)
+    }
+}
+
+/// Returns the index of the DDSketch bucket containing the positive value
+/// `val`.
+#[cfg(feature = "std")]
+fn key(val: f64, gamma: f64) -> i32 {
+    (val.ln() / gamma.ln()).ceil() as i32
+}
+
+/// Returns the bounds of the DDSketch bucket `index`, `γ^(index - index_offset - 1)`
+/// and `γ^(index - index_offset)`.
+#[cfg(feature = "std")]
+fn bounds(index: i32, index_offset: i32, gamma: f64) -> (f64, f64) {
+    // the exponent can overflow an `i32`, but not an `f64`'s integers.
+    let exponent = f64::from(index) - f64::from(index_offset);
+    (gamma.powf(exponent - 1.0), gamma.powf(exponent))
+}
+
+#[cfg(feature = "std")]
+impl From<&Histogram> for DDSketch {
+    /// Converts a histogram as by [`Histogram::to_ddsketch`], with a relative
+    /// accuracy of 1%.
+    fn from(histogram: &Histogram) -> Self {
+        histogram.to_ddsketch(0.01)
+    }
+}
+
+#[cfg(feature = "std")]
+impl TryFrom<&DDSketch> for Histogram {
+    type Error = DDSketchError;
+
+    /// Converts a DDSketch as by [`Histogram::from_ddsketch`], distributing
+    /// each bucket's values uniformly.
+    fn try_from(sketch: &DDSketch) -> Result<Self, Self::Error> {
+        Histogram::from_ddsketch(sketch, BucketDistribution::Uniform)
+    }
+}
+
+impl From<&Histogram> for TDigest {
+    /// Converts a histogram as by [`Histogram::to_tdigest`].
+    fn from(histogram: &Histogram) -> Self {
+        histogram.to_tdigest()
+    }
+}
+
+impl TryFrom<&TDigest> for Histogram {
+    type Error = TDigestError;
+
+    /// Converts a t-digest as by [`Histogram::from_tdigest`], distributing
+    /// each centroid's values uniformly.
+    fn try_from(digest: &TDigest) -> Result<Self, Self::Error> {
+        Histogram::from_tdigest(digest, BucketDistribution::Uniform)
+    }
+}
+
+// === impl DDSketch ===
+
+impl DDSketch {
+    /// The smallest relative accuracy, at which every bin's bucket index fits
+    /// in an `i32`.
+    pub const MIN_RELATIVE_ACCURACY: f64 = 1e-6;
+
+    /// Returns the relative accuracy of the sketch's buckets,
+    /// `(γ - 1) / (γ + 1)`.
+    #[must_use]
+    pub fn relative_accuracy(&self) -> f64 {
+        (self.gamma - 1.0) / (self.gamma + 1.0)
+    }
+}
+
+// === impl DDSketchError ===
+
+impl fmt::Display for DDSketchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidGamma(gamma) => {
+                write!(f, "invalid DDSketch gamma {gamma} (must be greater than 1)")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DDSketchError {}
+
+// === impl TDigestError ===
+
+impl fmt::Display for TDigestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidMean { index } => {
+                write!(
+                    f,
+                    "invalid or unsorted t-digest centroid mean at index {index}"
+                )
+            }
+            Self::InvalidRange { min, max } => write!(
+                f,
+                "t-digest range [{min}, {max}] doesn't contain its centroids"
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TDigestError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::{collection::vec, prelude::*, prop_assert, proptest};
+
+    const QUANTILES: [f64; 9] = [0.0, 0.01, 0.1, 0.25, 0.5, 0.75, 0.9, 0.99, 1.0];
+
+    proptest! {
+        #[test]
+        fn ddsketch_roundtrip_quantiles(histogram in histogram(), relative_accuracy in 0.001f64..0.1) {
+            let sketch = histogram.to_ddsketch(relative_accuracy);
+            let roundtrip = Histogram::try_from(&sketch).unwrap();
+            let expected = histogram.approx_quantiles(&QUANTILES).unwrap();
+            let actual = roundtrip.approx_quantiles(&QUANTILES).unwrap();
+            for (a, b) in expected.into_iter().zip(actual) {
+                // a value moves to one of the bins overlapping the bucket
+                // containing its bin's midpoint.
+                let (lower, upper) = containing_bins(&histogram, a)
+                    .into_iter()
+                    .map(|(_, bin, _)| bucket_bins(bin, sketch.gamma))
+                    .reduce(|(l1, u1), (l2, u2)| (l1.min(l2), u1.max(u2)))
+                    .unwrap();
+                prop_assert!(within(b, lower, upper), "{} not in [{}, {}] (from {})", b, lower, upper, a);
+            }
+        }
+
+        #[test]
+        fn tdigest_roundtrip_quantiles(histogram in histogram()) {
+            let digest = histogram.to_tdigest();
+            let roundtrip = Histogram::try_from(&digest).unwrap();
+            let expected = histogram.approx_quantiles(&QUANTILES).unwrap();
+            let actual = roundtrip.approx_quantiles(&QUANTILES).unwrap();
+            for (a, b) in expected.into_iter().zip(actual) {
+                // a value moves no further than the midpoints of the
+                // neighbouring non-empty bins.
+                let (lower, upper) = containing_bins(&histogram, a)
+                    .into_iter()
+                    .map(|(prev, _, next)| (prev.lower_bound(), next.upper_bound()))
+                    .reduce(|(l1, u1), (l2, u2)| (l1.min(l2), u1.max(u2)))
+                    .unwrap();
+                prop_assert!(within(b, lower, upper), "{} not in [{}, {}] (from {})", b, lower, upper, a);
+            }
+        }
+    }
+
+    /// Returns histograms of positive and negative values of many magnitudes,
+    /// and zeros, without NaN values.
+    fn histogram() -> impl Strategy<Value = Histogram> {
+        let value = prop_oneof![
+            1 => Just(0.0),
+            10 => (any::<bool>(), -20.0f64..20.0)
+                .prop_map(|(neg, exp)| if neg { -(10f64.powf(exp)) } else { 10f64.powf(exp) }),
+        ];
+        vec((value, 1i64..1000), 1..100).prop_map(|values| {
+            let mut histogram = Histogram::new();
+            for (val, n) in values {
+                histogram.record_f64s(val, n).unwrap();
+            }
+            histogram
+        })
+    }
+
+    /// Returns each non-empty bin whose bounds include `val`, with the
+    /// non-empty bins before and after it, or itself if there are none.
+    fn containing_bins(histogram: &Histogram, val: f64) -> Vec<(Bin, Bin, Bin)> {
+        let bins: Vec<Bin> = histogram.bins.iter().map(|bucket| bucket.bin).collect();
+        (0..bins.len())
+            .filter(|&i| within(val, bins[i].lower_bound(), bins[i].upper_bound()))
+            .map(|i| {
+                let next = (i + 1).min(bins.len() - 1);
+                (bins[i.saturating_sub(1)], bins[i], bins[next])
+            })
+            .collect()
+    }
+
+    /// Returns `true` if `val` is between `lower` and `upper`, allowing for the
+    /// rounding error of quantiles computed from bins' floating-point bounds.
+    fn within(val: f64, lower: f64, upper: f64) -> bool {
+        let slop = |bound: f64| bound.abs() * 1e-12;
+        lower - slop(lower) <= val && val <= upper + slop(upper)
+    }
+
+    /// Returns the lower bound of the first bin, and the upper bound of the
+    /// last, overlapping the DDSketch bucket containing `bin`'s midpoint.
+    fn bucket_bins(bin: Bin, gamma: f64) -> (f64, f64) {
+        let midpoint = bin.midpoint();
+        if midpoint == 0.0 {
+            return (0.0, 0.0);
+        }
+        let (lower, upper) = bounds(key(midpoint.abs(), gamma), 0, gamma);
+        let (lower, upper) = if midpoint < 0.0 {
+            (-upper, -lower)
+        } else {
+            (lower, upper)
+        };
+        (
+            Bin::for_value(lower).lower_bound(),
+            Bin::for_value(upper).upper_bound(),
+        )
+    }
+}
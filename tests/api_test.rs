@@ -1,4 +1,4 @@
-use circllhist::Histogram;
+use circllhist::{Bin, Histogram, QuantileMethod};
 mod util;
 // package circonusllhist_test
 
@@ -46,10 +46,40 @@ const STRINGS: &[&str] = &[
 // 		}
 // 	}
 // }
+#[test]
+fn dec_strings() {
+    let mut h = Histogram::default();
+    for sample in S1 {
+        h.record(*sample).unwrap();
+    }
+    assert_eq!(h.dec_strings(), STRINGS);
+
+    let mut h = Histogram::default();
+    for val in [f64::NAN, -1.0, -0.0123, 1.7e-100, 5.5e20, 1e127] {
+        h.record(val).unwrap();
+    }
+    let expected = [
+        "H[NaN]=1",
+        "H[-1.0e+00]=1",
+        "H[-1.2e-02]=1",
+        "H[1.7e-100]=1",
+        "H[5.5e+20]=1",
+        "H[1.0e+127]=1",
+    ];
+    assert_eq!(h.dec_strings(), expected);
+    assert_eq!(Histogram::from_strs(h.dec_strings()).unwrap(), h);
+
+    let canonical = h
+        .display_bins()
+        .map(|bin| bin.canonical().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(canonical, expected);
+}
+
 #[test]
 fn display_buckets() {
     // `fmt::lowerExp` formats things *slightly* differently than Go's `%e` format,
-    // but it should still parse.
+    // but it should still parse. `dec_strings` produces Go's format.
     const RUST_STRINGS: &[&str] = &[
         "H[0.0e0]=1",
         "H[1.2e-1]=2",
@@ -113,6 +143,241 @@ fn from_strs() {
     assert_ne!(single_hist, double_hist)
 }
 
+// func TestSerialize(t *testing.T) {
+// 	h, h2 := hist.New(), hist.New()
+// 	for _, sample := range s1 {
+// 		_ = h.RecordValue(sample)
+// 	}
+// 	var buf bytes.Buffer
+// 	if err := h.Serialize(&buf); err != nil {
+// 		t.Error(err)
+// 	}
+// 	h2, err := hist.Deserialize(&buf)
+// 	if err != nil {
+// 		t.Error(err)
+// 	}
+// 	if !h.Equals(h2) {
+// 		t.Log(h.DecStrings())
+// 		t.Log(h2.DecStrings())
+// 		t.Error("histograms do not match")
+// 	}
+// }
+#[test]
+fn serialize() {
+    let mut h = Histogram::default();
+    for sample in S1 {
+        h.record(*sample)
+            .expect("value should be recorded successfully");
+    }
+
+    let mut buf = Vec::new();
+    h.serialize_into(&mut buf)
+        .expect("serializing into a `Vec` should succeed");
+    assert_eq!(buf, h.to_bytes());
+
+    let h2 = Histogram::deserialize(&buf).expect("histogram should deserialize");
+    assert_eq!(h, h2, "histograms do not match");
+}
+
+#[test]
+fn serialize_bytes() {
+    let mut h = Histogram::default();
+    for sample in S1 {
+        h.record(*sample)
+            .expect("value should be recorded successfully");
+    }
+    h.record_f64s(1.0, 256).unwrap();
+    h.record_f64s(5.5e20, 0x1_0000_0000).unwrap();
+
+    #[rustfmt::skip]
+    const EXPECTED: &[u8] = &[
+        // number of bins
+        0x00, 0x09,
+        // val, exp, count width - 1, count
+        0, 0, 0, 1,
+        12, 0xff, 0, 2,
+        13, 0xff, 0, 1,
+        22, 0xff, 0, 1,
+        32, 0xff, 0, 1,
+        41, 0xff, 0, 2,
+        43, 0xff, 0, 1,
+        10, 0, 1, 0x01, 0x00,
+        55, 20, 4, 0x01, 0x00, 0x00, 0x00, 0x00,
+    ];
+    assert_eq!(h.to_bytes(), EXPECTED);
+    assert_eq!(Histogram::deserialize(EXPECTED), Ok(h));
+}
+
+#[test]
+fn deserialize_coalesces_bins() {
+    // the same bin, twice, out of order with a second bin.
+    let bytes = [0x00, 0x03, 20, 0, 0, 1, 10, 0, 0, 3, 20, 0, 1, 0x01, 0x00];
+    let expected = Histogram::from_strs(["H[1.0e+00]=3", "H[2.0e+00]=257"]).unwrap();
+    assert_eq!(Histogram::deserialize(&bytes), Ok(expected));
+}
+
+#[test]
+fn deserialize_empty() {
+    let h = Histogram::deserialize(&[0x00, 0x00]).expect("histogram should deserialize");
+    assert!(h.is_empty());
+    assert_eq!(Histogram::new().to_bytes(), [0x00, 0x00]);
+}
+
+#[test]
+fn zero_count_bins() {
+    // removing the only value leaves its bin with a count of zero, which is
+    // not serialized.
+    let mut h = Histogram::new();
+    h.record(1.0).unwrap().record_f64s(1.0, -1).unwrap();
+    assert!(h.is_empty());
+    assert_eq!(h, Histogram::new());
+    assert_eq!(h.to_bytes(), [0x00, 0x00]);
+    assert_eq!(Histogram::deserialize(&h.to_bytes()), Ok(h));
+
+    let mut h = Histogram::from_strs(STRINGS).unwrap();
+    h.record(1.0).unwrap().record_f64s(1.0, -1).unwrap();
+    assert!(!h.is_empty());
+    assert_eq!(h, Histogram::from_strs(STRINGS).unwrap());
+    assert_eq!(Histogram::deserialize(&h.to_bytes()), Ok(h));
+}
+
+#[test]
+fn deserialize_errors() {
+    use circllhist::DecodeError;
+
+    assert_eq!(
+        Histogram::deserialize(&[0x00]),
+        Err(DecodeError::Truncated {
+            offset: 1,
+            needed: 1
+        })
+    );
+    // two bins, but only one is present.
+    assert_eq!(
+        Histogram::deserialize(&[0x00, 0x02, 10, 0, 0, 1]),
+        Err(DecodeError::Truncated {
+            offset: 6,
+            needed: 3
+        })
+    );
+    // the count is four bytes wide, but only two are present.
+    assert_eq!(
+        Histogram::deserialize(&[0x00, 0x01, 10, 0, 3, 1, 2]),
+        Err(DecodeError::Truncated {
+            offset: 7,
+            needed: 2
+        })
+    );
+    assert_eq!(
+        Histogram::deserialize(&[0x00, 0x01, 10, 0, 8, 1, 2, 3, 4, 5, 6, 7, 8, 9]),
+        Err(DecodeError::InvalidCountWidth {
+            offset: 4,
+            width: 8
+        })
+    );
+    assert_eq!(
+        Histogram::deserialize(&[0x00, 0x01, 5, 3, 0, 1]),
+        Err(DecodeError::InvalidBin {
+            offset: 2,
+            val: 5,
+            exp: 3
+        })
+    );
+    assert_eq!(
+        Histogram::deserialize(&[0x00, 0x01, 10, 0, 0, 1, 0xff]),
+        Err(DecodeError::TrailingBytes { offset: 6 })
+    );
+}
+
+// func TestSerializeB64(t *testing.T) {
+// 	h, h2 := hist.New(), hist.New()
+// 	for _, sample := range s1 {
+// 		_ = h.RecordValue(sample)
+// 	}
+// 	hb64, err := h.SerializeB64()
+// 	if err != nil {
+// 		t.Error(err)
+// 	}
+// 	h2, err = hist.DeserializeB64(hb64)
+// 	if err != nil {
+// 		t.Error(err)
+// 	}
+// 	if !h.Equals(h2) {
+// 		t.Log(h.DecStrings())
+// 		t.Log(h2.DecStrings())
+// 		t.Error("histograms do not match")
+// 	}
+// }
+#[test]
+fn serialize_b64() {
+    let mut h = Histogram::default();
+    for sample in S1 {
+        h.record(*sample)
+            .expect("value should be recorded successfully");
+    }
+
+    let b64 = h.to_b64();
+    assert_eq!(b64, "AAcAAAABDP8AAg3/AAEW/wABIP8AASn/AAIr/wAB");
+    let h2 = Histogram::from_b64(&b64).expect("histogram should deserialize");
+    assert_eq!(h, h2, "histograms do not match");
+}
+
+#[test]
+fn b64_vectors() {
+    // a single sample of 1.0
+    let mut one = Histogram::new();
+    one.record(1.0).unwrap();
+    // the same histogram as `serialize_bytes`, which includes wider counts
+    let mut wide = Histogram::from_strs(STRINGS).unwrap();
+    wide.record_f64s(1.0, 256).unwrap();
+    wide.record_f64s(5.5e20, 0x1_0000_0000).unwrap();
+
+    for (b64, expected) in [
+        ("AAA=", Histogram::new()),
+        ("AAEKAAAB", one),
+        (
+            "AAkAAAABDP8AAg3/AAEW/wABIP8AASn/AAIr/wABCgABAQA3FAQBAAAAAA==",
+            wide,
+        ),
+    ] {
+        assert_eq!(expected.to_b64(), b64);
+        assert_eq!(Histogram::from_b64(b64).as_ref(), Ok(&expected), "{b64}");
+        // the padding is optional
+        let unpadded = b64.trim_end_matches('=');
+        assert_eq!(
+            Histogram::from_b64_bytes(unpadded.as_bytes()),
+            Ok(expected),
+            "{unpadded}"
+        );
+    }
+}
+
+#[test]
+fn b64_errors() {
+    use circllhist::DecodeError;
+
+    assert_eq!(
+        Histogram::from_b64("AAEK*AAB"),
+        Err(DecodeError::InvalidBase64 { offset: 4 })
+    );
+    assert_eq!(
+        Histogram::from_b64("AA=A"),
+        Err(DecodeError::InvalidBase64 { offset: 2 })
+    );
+    assert_eq!(
+        Histogram::from_b64("AAEKA"),
+        Err(DecodeError::InvalidBase64 { offset: 4 })
+    );
+    // valid base64, but not a complete histogram
+    assert_eq!(
+        Histogram::from_b64("AAEKAA"),
+        Err(DecodeError::Truncated {
+            offset: 4,
+            needed: 1
+        })
+    );
+}
+
 // func TestMean(t *testing.T) {
 #[test]
 fn mean() {
@@ -175,6 +440,722 @@ fn quantiles6() {
     util::test_quantiles(&[0.0, 0.1], [0.0, 0.1], [0.0, 0.0])
 }
 
+#[test]
+fn quantiles_negative() {
+    // -1.0 is in the bin (-1.1, -1.0]
+    util::test_quantiles(&[-1.0], [0.0, 0.5, 1.0], [-1.1, -1.05, -1.0]);
+    util::test_quantiles(
+        &[-1.0, -0.01, 0.0, 1.0],
+        [0.0, 0.5, 1.0],
+        [-1.1, -0.01, 1.1],
+    );
+}
+
+// the values in a bin are assumed to be evenly spaced inside it, so the single
+// value in [1.0, 1.1) is at 1.05, the two values in [0.12, 0.13) are at
+// 0.12333 and 0.12667, and so on. the expected quantiles are worked out by hand
+// from that model and the type 7 definition; they are not ported from
+// libcircllhist's tests.
+#[test]
+fn quantiles7_single() {
+    util::test_quantiles_with(
+        QuantileMethod::Type7,
+        &[1.0],
+        [0.0, 0.25, 0.5, 1.0],
+        [1.05, 1.05, 1.05, 1.05],
+    );
+}
+
+#[test]
+fn quantiles7_s1() {
+    util::test_quantiles_with(
+        QuantileMethod::Type7,
+        S1,
+        [0.0, 0.1, 0.5, 0.95, 0.99, 1.0],
+        [0.0, 0.0986667, 0.225, 0.4276667, 0.4335333, 0.435],
+    );
+}
+
+#[test]
+fn quantiles7_interpolates_between_bins() {
+    util::test_quantiles_with(
+        QuantileMethod::Type7,
+        &[1.0, 2.0],
+        [0.0, 0.25, 0.5, 1.0],
+        [1.05, 1.3, 1.55, 2.05],
+    );
+    // values too large for a bin are NaN, and are ignored.
+    util::test_quantiles_with(
+        QuantileMethod::Type7,
+        &[1.0, 1e200],
+        [0.0, 1.0],
+        [1.05, 1.05],
+    );
+    util::test_quantiles_with(
+        QuantileMethod::Type7,
+        &[1e200, 1e200, 1e200, 0.0, 0.0, 1e-20, 1e-20, 1e-20, 1e-10],
+        [0.0, 0.5, 1.0],
+        [0.0, 1.0375e-20, 1.05e-10],
+    );
+}
+
+#[test]
+fn quantiles7_negative() {
+    util::test_quantiles_with(
+        QuantileMethod::Type7,
+        &[-1.0, 1.0],
+        [0.0, 0.5, 1.0],
+        [-1.05, 0.0, 1.05],
+    );
+    util::test_quantiles_with(
+        QuantileMethod::Type7,
+        &[-2.0, -1.0, -1.0, -1.0],
+        [0.0, 0.5, 1.0],
+        [-2.05, -1.0625, -1.025],
+    );
+}
+
+#[test]
+fn quantiles7_large_counts() {
+    // the total count is `u64::MAX`, too large to be exact in an `f64`.
+    let mut h = Histogram::default();
+    h.record_f64s(1.0, i64::MAX)
+        .unwrap()
+        .record_f64s(2.0, i64::MAX)
+        .unwrap()
+        .record(3.0)
+        .unwrap();
+    let [min, median, max] = h
+        .approx_quantiles_with(QuantileMethod::Type7, &[0.0, 0.5, 1.0])
+        .unwrap();
+    assert_approx_eq!(min, 1.0);
+    assert_approx_eq!(median, 2.0);
+    assert_approx_eq!(max, 3.05);
+}
+
+#[test]
+fn quantiles7_errors() {
+    use circllhist::{QuantileError, QuantilesError};
+
+    let h = Histogram::default();
+    assert!(matches!(
+        h.quantile_with(QuantileMethod::Type7, 0.5),
+        Err(QuantileError::EmptyHistogram)
+    ));
+
+    let h = Histogram::from_strs(STRINGS).unwrap();
+    assert!(matches!(
+        h.quantile_with(QuantileMethod::Type7, 1.5),
+        Err(QuantileError::OutOfBounds(q)) if q == 1.5
+    ));
+    assert!(matches!(
+        h.approx_quantiles_with(QuantileMethod::Type7, &[0.5, 0.1]),
+        Err(QuantilesError::NotSorted)
+    ));
+}
+
+#[test]
+fn inverse_quantiles() {
+    let h = Histogram::from_strs(STRINGS).unwrap();
+    let values = [
+        f64::NEG_INFINITY,
+        -1.0,
+        0.0,
+        0.125,
+        0.135,
+        0.5,
+        f64::INFINITY,
+    ];
+    let expected = [0.0, 0.0, 1.0, 2.0, 3.5, 9.0, 9.0].map(|count| count / 9.0);
+    let qs = h.approx_inverse_quantiles(&values).unwrap();
+    for ((value, q), expected) in values.iter().zip(qs).zip(expected) {
+        assert_approx_eq!(q, expected, "inverse quantile of {value}");
+        assert_eq!(h.approx_inverse_quantile(*value).unwrap(), q);
+    }
+
+    let mut h = Histogram::default();
+    h.record(-1.0).unwrap();
+    h.record(1.0).unwrap();
+    assert_approx_eq!(h.approx_inverse_quantile(-1.05).unwrap(), 0.25);
+    assert_approx_eq!(h.approx_inverse_quantile(0.0).unwrap(), 0.5);
+    assert_approx_eq!(h.approx_inverse_quantile(1.05).unwrap(), 0.75);
+}
+
+#[test]
+fn inverse_quantiles_roundtrip() {
+    let h = Histogram::from_strs(STRINGS).unwrap();
+    for q in [0.2, 0.3, 0.5, 0.7, 0.9, 1.0] {
+        let value = h.quantile(q).unwrap();
+        let inverse = h.approx_inverse_quantile(value).unwrap();
+        assert_approx_eq!(inverse, q, "inverse quantile of {value}");
+    }
+}
+
+#[test]
+fn inverse_quantiles_large_counts() {
+    // the total count is more than `u64::MAX`, so it saturates.
+    let mut h = Histogram::default();
+    for value in [1.0, 2.0, 3.0, 4.0] {
+        h.record_f64s(value, i64::MAX).unwrap();
+    }
+    assert_eq!(h.approx_inverse_quantile(0.5).unwrap(), 0.0);
+    assert_approx_eq!(h.approx_inverse_quantile(1.05).unwrap(), 0.25);
+    assert_eq!(h.approx_inverse_quantile(5.0).unwrap(), 1.0);
+}
+
+#[test]
+fn inverse_quantiles_errors() {
+    use circllhist::{QuantileError, QuantilesError};
+
+    assert!(matches!(
+        Histogram::default().approx_inverse_quantile(1.0),
+        Err(QuantileError::EmptyHistogram)
+    ));
+
+    let h = Histogram::from_strs(STRINGS).unwrap();
+    assert!(matches!(
+        h.approx_inverse_quantile(f64::NAN),
+        Err(QuantileError::OutOfBounds(v)) if v.is_nan()
+    ));
+    assert!(matches!(
+        h.approx_inverse_quantiles(&[0.5, 0.1]),
+        Err(QuantilesError::NotSorted)
+    ));
+}
+
+#[test]
+fn approx_counts() {
+    let mut h = Histogram::from_strs(STRINGS).unwrap();
+    h.record_f64s(f64::NAN, 2).unwrap();
+
+    assert_eq!(h.approx_count_below(-1.0), 0);
+    assert_eq!(h.approx_count_below(0.0), 1);
+    assert_eq!(h.approx_count_below(0.125), 1);
+    assert_eq!(h.approx_count_below(0.135), 3);
+    assert_eq!(h.approx_count_below(1.0), 9);
+    assert_eq!(h.approx_count_below(f64::INFINITY), 9);
+    assert_eq!(h.approx_count_below(f64::NAN), 0);
+
+    assert_eq!(h.approx_count_above(f64::NEG_INFINITY), 9);
+    assert_eq!(h.approx_count_above(0.0), 9);
+    assert_eq!(h.approx_count_above(0.125), 6);
+    assert_eq!(h.approx_count_above(0.5), 0);
+    assert_eq!(h.approx_count_above(f64::NAN), 0);
+
+    assert_eq!(h.approx_count_between(f64::NEG_INFINITY, f64::INFINITY), 9);
+    assert_eq!(h.approx_count_between(0.125, 0.415), 3);
+    assert_eq!(h.approx_count_between(0.125, 0.126), 0);
+    assert_eq!(h.approx_count_between(0.5, 0.1), 0);
+    assert_eq!(h.approx_count_between(f64::NAN, 1.0), 0);
+
+    assert_eq!(h.count_nearby(0.0), 1);
+    assert_eq!(h.count_nearby(0.125), 2);
+    assert_eq!(h.count_nearby(0.129), 2);
+    assert_eq!(h.count_nearby(0.5), 0);
+    // NaN and out of range values are both counted in the NaN bin.
+    assert_eq!(h.count_nearby(f64::NAN), 2);
+    assert_eq!(h.count_nearby(1e200), 2);
+}
+
+#[test]
+fn approx_counts_negative() {
+    let mut h = Histogram::default();
+    h.record_f64s(-1.0, 2).unwrap();
+    h.record(-0.5).unwrap();
+    h.record(1.0).unwrap();
+
+    assert_eq!(h.approx_count_below(-0.505), 2);
+    assert_eq!(h.approx_count_below(-0.4), 3);
+    assert_eq!(h.approx_count_above(-0.505), 1);
+    assert_eq!(h.approx_count_above(-2.0), 4);
+    assert_eq!(h.approx_count_between(-2.0, 0.0), 3);
+    assert_eq!(h.count_nearby(-1.05), 2);
+}
+
+#[test]
+fn approx_moments() {
+    // the midpoints are 1.05 and 3.05.
+    let mut h = Histogram::default();
+    h.record(1.0).unwrap();
+    h.record(3.0).unwrap();
+    assert_approx_eq!(h.approx_moment(1.0), 2.05);
+    assert_approx_eq!(h.approx_variance(), 1.0);
+    assert_approx_eq!(h.approx_stddev(), 1.0);
+    assert!(h.approx_skewness().abs() < 1e-9);
+    assert_approx_eq!(h.approx_kurtosis(), -2.0);
+
+    // a bin whose values have all been removed doesn't count, even as the
+    // first bin.
+    let mut h2 = Histogram::default();
+    h2.record(0.5).unwrap().record_f64s(0.5, -1).unwrap();
+    h2.merge_from(&h);
+    assert_approx_eq!(h2.approx_moment(1.0), 2.05);
+    assert_approx_eq!(h2.approx_variance(), 1.0);
+    assert_approx_eq!(h2.approx_stddev(), 1.0);
+    assert!(h2.approx_skewness().abs() < 1e-9);
+    assert_approx_eq!(h2.approx_kurtosis(), -2.0);
+
+    // the midpoints are 1.05 (three times) and 5.05, so the deviations from
+    // the mean of 2.05 are -1 (three times) and 3.
+    let mut h = Histogram::default();
+    h.record_f64s(1.0, 3).unwrap();
+    h.record(5.0).unwrap();
+    assert_approx_eq!(h.approx_moment(1.0), 2.05);
+    assert_approx_eq!(h.approx_moment(2.0), 7.2025);
+    assert_approx_eq!(h.approx_variance(), 3.0);
+    assert_approx_eq!(h.approx_stddev(), 3f64.sqrt());
+    assert_approx_eq!(h.approx_skewness(), 2.0 / 3f64.sqrt());
+    assert_approx_eq!(h.approx_kurtosis(), 4.0 * 84.0 / 144.0 - 3.0);
+
+    // NaN values are ignored.
+    h.record(f64::NAN).unwrap();
+    assert_approx_eq!(h.approx_variance(), 3.0);
+
+    // skewness and kurtosis are undefined if every value is the same.
+    let mut h = Histogram::default();
+    h.record_f64s(-1.0, 10).unwrap();
+    assert_approx_eq!(h.approx_moment(3.0), -1.157625);
+    assert_eq!(h.approx_variance(), 0.0);
+    assert!(h.approx_skewness().is_nan());
+    assert!(h.approx_kurtosis().is_nan());
+
+    let h = Histogram::default();
+    assert!(h.approx_moment(1.0).is_nan());
+    assert!(h.approx_variance().is_nan());
+    assert!(h.approx_stddev().is_nan());
+    assert!(h.approx_skewness().is_nan());
+    assert!(h.approx_kurtosis().is_nan());
+}
+
+#[test]
+fn approx_moments_wide() {
+    // the midpoints are 1.05e-9 and 1.05e9, a million times each.
+    let mut h = Histogram::default();
+    h.record_f64s(1e-9, 1_000_000).unwrap();
+    h.record_f64s(1e9, 1_000_000).unwrap();
+    let half_spread = (1.05e9 - 1.05e-9) / 2.0;
+    assert_approx_eq!(h.approx_moment(1.0), (1.05e9 + 1.05e-9) / 2.0);
+    assert_approx_eq!(h.approx_variance(), half_spread * half_spread);
+    assert!(h.approx_skewness().abs() < 1e-9);
+    assert_approx_eq!(h.approx_kurtosis(), -2.0);
+
+    // a tight cluster of very large values, where summing squares would
+    // cancel catastrophically.
+    let mut h = Histogram::default();
+    h.record_f64s(1.0e15, 1_000_000_000_000).unwrap();
+    h.record_f64s(1.1e15, 1_000_000_000_000).unwrap();
+    assert_approx_eq!(h.approx_stddev(), 0.5e14);
+}
+
+#[test]
+fn clamp() {
+    let mut h = Histogram::from_strs(STRINGS).unwrap();
+    h.record(f64::NAN).unwrap();
+
+    let expected = Histogram::from_strs([
+        "H[NaN]=1",
+        "H[1.2e-01]=3",
+        "H[1.3e-01]=1",
+        "H[2.2e-01]=1",
+        "H[3.2e-01]=1",
+        "H[4.0e-01]=3",
+    ])
+    .unwrap();
+    assert_eq!(h.clamp(0.125, 0.4), expected);
+
+    // bins containing the bounds are left as they are.
+    let expected = Histogram::from_strs([
+        "H[NaN]=1",
+        "H[1.2e-01]=3",
+        "H[1.3e-01]=1",
+        "H[2.2e-01]=1",
+        "H[3.2e-01]=1",
+        "H[4.1e-01]=3",
+    ])
+    .unwrap();
+    assert_eq!(h.clamp(0.125, 0.415), expected);
+
+    assert_eq!(h.clamp(f64::NEG_INFINITY, f64::INFINITY), h);
+    assert_eq!(h.clamp(-1e200, 1e200), h);
+    assert_eq!(h.clamp(0.0, 1.0), h);
+
+    let mut h = Histogram::default();
+    for val in [-5.0, -1.0, 1.0, 5.0] {
+        h.record(val).unwrap();
+    }
+    let expected = Histogram::from_strs([
+        "H[-2.0e+00]=1",
+        "H[-1.0e+00]=1",
+        "H[1.0e+00]=1",
+        "H[2.0e+00]=1",
+    ])
+    .unwrap();
+    assert_eq!(h.clamp(-2.0, 2.0), expected);
+    let expected = Histogram::from_strs(["H[3.0e+00]=4"]).unwrap();
+    assert_eq!(h.clamp(3.0, 3.0), expected);
+}
+
+#[test]
+#[should_panic(expected = "invalid clamp bounds")]
+fn clamp_invalid_bounds() {
+    let h = Histogram::from_strs(STRINGS).unwrap();
+    let _ = h.clamp(0.4, 0.1);
+}
+
+#[test]
+fn retain_range() {
+    let mut h = Histogram::from_strs(STRINGS).unwrap();
+    h.record(f64::NAN).unwrap();
+
+    let retained = |range: std::ops::Range<f64>| {
+        let mut h = h.clone();
+        h.retain_range(range);
+        h
+    };
+    let expected = Histogram::from_strs([
+        "H[1.2e-01]=2",
+        "H[1.3e-01]=1",
+        "H[2.2e-01]=1",
+        "H[3.2e-01]=1",
+        "H[4.1e-01]=2",
+    ])
+    .unwrap();
+    assert_eq!(retained(0.125..0.415), expected);
+    assert_eq!(retained(0.5..0.1), Histogram::default());
+    assert_eq!(
+        retained(f64::NEG_INFINITY..f64::INFINITY),
+        Histogram::from_strs(STRINGS).unwrap()
+    );
+
+    let mut below = h.clone();
+    below.retain_range(..0.2);
+    let expected = Histogram::from_strs(["H[0.0e+00]=1", "H[1.2e-01]=2", "H[1.3e-01]=1"]).unwrap();
+    assert_eq!(below, expected);
+
+    let mut above = h.clone();
+    above.retain_range(0.3..);
+    let expected = Histogram::from_strs(["H[3.2e-01]=1", "H[4.1e-01]=2", "H[4.3e-01]=1"]).unwrap();
+    assert_eq!(above, expected);
+
+    let mut all = h.clone();
+    all.retain_range(..);
+    assert_eq!(all, Histogram::from_strs(STRINGS).unwrap());
+
+    let mut h = Histogram::default();
+    for val in [-5.0, -1.0, 1.0, 5.0] {
+        h.record(val).unwrap();
+    }
+    h.retain_range(-1.05..=1.05);
+    let expected = Histogram::from_strs(["H[-1.0e+00]=1", "H[1.0e+00]=1"]).unwrap();
+    assert_eq!(h, expected);
+}
+
+#[test]
+fn compress_mbe() {
+    let mut h = Histogram::from_strs(STRINGS).unwrap();
+    h.record(f64::NAN).unwrap();
+    h.record_f64s(1234.0, 2).unwrap();
+
+    let expected = Histogram::from_strs([
+        "H[NaN]=1",
+        "H[0.0e+00]=1",
+        "H[1.0e-01]=3",
+        "H[2.0e-01]=1",
+        "H[3.0e-01]=1",
+        "H[4.0e-01]=3",
+        "H[1.2e+03]=2",
+    ])
+    .unwrap();
+    assert_eq!(h.compress_mbe(-1), expected);
+
+    let expected = Histogram::from_strs(["H[NaN]=1", "H[0.0e+00]=9", "H[1.0e+03]=2"]).unwrap();
+    assert_eq!(h.compress_mbe(3), expected);
+    let expected = Histogram::from_strs(["H[NaN]=1", "H[0.0e+00]=11"]).unwrap();
+    assert_eq!(h.compress_mbe(4), expected);
+    assert_eq!(h.compress_mbe(-2), h);
+    assert_eq!(h.compress_mbe(i8::MIN), h);
+
+    let mut h = Histogram::default();
+    for val in [-0.12, -0.012, 0.012, 0.12] {
+        h.record(val).unwrap();
+    }
+    let expected = Histogram::from_strs(["H[-1.0e-01]=1", "H[0.0e+00]=2", "H[1.0e-01]=1"]).unwrap();
+    assert_eq!(h.compress_mbe(-1), expected);
+}
+
+#[test]
+fn buckets() {
+    let mut h = Histogram::default();
+    for val in [f64::NAN, -1.0, -1.0, 0.0, 0.123, 5.5e20] {
+        h.record(val).unwrap();
+    }
+    let buckets = h.buckets().collect::<Vec<_>>();
+    assert_eq!(buckets.len(), 5);
+
+    let nan = buckets[0];
+    assert!(nan.is_nan());
+    assert_eq!(nan.count(), 1);
+    assert!(nan.lower_bound().is_nan());
+    assert!(nan.upper_bound().is_nan());
+    assert!(nan.midpoint().is_nan());
+    assert!(nan.width().is_nan());
+
+    let bounds = buckets[1..]
+        .iter()
+        .map(|b| {
+            assert!(!b.is_nan());
+            (
+                b.lower_bound(),
+                b.upper_bound(),
+                b.midpoint(),
+                b.width(),
+                b.count(),
+            )
+        })
+        .collect::<Vec<_>>();
+    let expected = [
+        (-1.1, -1.0, -1.05, 0.1, 2),
+        (0.0, 0.0, 0.0, 0.0, 1),
+        (0.12, 0.13, 0.125, 0.01, 1),
+        (5.5e20, 5.6e20, 5.55e20, 1e19, 1),
+    ];
+    for (actual, expected) in bounds.iter().zip(expected) {
+        assert_approx_eq!(actual.0, expected.0, "lower bound");
+        assert_approx_eq!(actual.1, expected.1, "upper bound");
+        assert_approx_eq!(actual.2, expected.2, "midpoint");
+        assert_approx_eq!(actual.3, expected.3, "width");
+        assert_eq!(actual.4, expected.4, "count");
+    }
+
+    let displayed = h
+        .buckets()
+        .map(|b| b.display().to_string())
+        .collect::<Vec<_>>();
+    let expected = h.display_bins().map(|b| b.to_string()).collect::<Vec<_>>();
+    assert_eq!(displayed, expected);
+}
+
+#[test]
+fn bin_lookup() {
+    let bin = Bin::for_value(1.234);
+    assert_eq!(Bin::for_int_scale(1234, -3), bin);
+    assert_eq!(Bin::for_int_scale(12, -1), bin);
+    assert_eq!(Bin::for_int_scale(1_299_999, -6), bin);
+    assert_approx_eq!(bin.lower_bound(), 1.2);
+    assert_approx_eq!(bin.upper_bound(), 1.3);
+    assert_approx_eq!(bin.midpoint(), 1.25);
+    assert_approx_eq!(bin.width(), 0.1);
+
+    assert_eq!(bin.next(), Some(Bin::for_value(1.3)));
+    assert_eq!(bin.prev(), Some(Bin::for_value(1.1)));
+    assert_eq!(Bin::for_value(9.9).next(), Some(Bin::for_value(10.0)));
+    assert_eq!(Bin::for_value(-1.0).next(), Some(Bin::for_value(-0.99)));
+    assert_eq!(Bin::for_value(-1.0).prev(), Some(Bin::for_value(-1.1)));
+
+    assert_eq!(Bin::for_value(0.0), Bin::ZERO);
+    assert_eq!(Bin::for_int_scale(0, 10), Bin::ZERO);
+    assert_eq!(Bin::for_value(f64::NAN), Bin::NAN);
+    assert_eq!(Bin::for_value(f64::INFINITY), Bin::NAN);
+    assert_eq!(Bin::for_int_scale(-12, 200), Bin::NAN);
+    assert_eq!(Bin::for_int_scale(12, -200), Bin::ZERO);
+    assert!(Bin::NAN.is_nan());
+
+    // indices are dense and sorted.
+    assert_eq!(Bin::NAN.index(), 0);
+    assert_eq!(Bin::from_index(bin.index()), Some(bin));
+    assert_eq!(Bin::from_index(bin.index() + 1), bin.next());
+    assert_eq!(Bin::all().len(), Bin::COUNT as usize);
+    assert!(Bin::all().skip(1).all(|bin| !bin.is_nan()));
+
+    let mut h = Histogram::default();
+    h.record_int_scale(1234, -3).unwrap();
+    h.record_int_scale(-1234, -3).unwrap();
+    let bins = h.buckets().map(|b| b.bin()).collect::<Vec<_>>();
+    assert_eq!(bins, [Bin::for_value(-1.234), bin]);
+}
+
+#[test]
+fn record_strict() {
+    use circllhist::{RecordError, RecordPolicy};
+
+    let mut h = Histogram::with_policy(RecordPolicy::Strict);
+    assert_eq!(h.policy(), RecordPolicy::Strict);
+    h.record(1.0)
+        .unwrap()
+        .record(0.0)
+        .unwrap()
+        .record(-1e-100)
+        .unwrap();
+    h.record_int_scale(123, -2).unwrap();
+
+    let expected = h.clone();
+    assert!(matches!(
+        h.record(f64::NAN),
+        Err(RecordError::NotFinite(v)) if v.is_nan()
+    ));
+    assert_eq!(
+        h.record(f64::NEG_INFINITY),
+        Err(RecordError::NotFinite(f64::NEG_INFINITY))
+    );
+    assert_eq!(h.record(1e200), Err(RecordError::Overflow(1e200)));
+    assert_eq!(h.record(-1e-200), Err(RecordError::Underflow(-1e-200)));
+    assert_eq!(
+        h.record(f64::MIN_POSITIVE),
+        Err(RecordError::Underflow(f64::MIN_POSITIVE))
+    );
+    assert!(matches!(
+        h.record_int_scale(5, 200),
+        Err(RecordError::Overflow(val)) if (val / 5e200 - 1.0).abs() < 1e-12
+    ));
+    assert!(matches!(
+        h.record_int_scale(-5, -200),
+        Err(RecordError::Underflow(val)) if (val / -5e-200 - 1.0).abs() < 1e-12
+    ));
+    assert_eq!(
+        h.record_int_scale(5, i32::MAX),
+        Err(RecordError::Overflow(f64::INFINITY))
+    );
+    assert_eq!(h, expected);
+
+    // 1 + 2 * i64::MAX is exactly u64::MAX.
+    h.record_f64s(1.0, i64::MAX).unwrap();
+    h.record_f64s(1.0, i64::MAX).unwrap();
+    let expected = h.clone();
+    assert_eq!(
+        h.record(1.0),
+        Err(RecordError::CountSaturated {
+            bin: Bin::for_value(1.0)
+        })
+    );
+    assert_eq!(h, expected);
+    // removing values can't saturate the count.
+    h.record_f64s(1.0, -1).unwrap();
+
+    // the same values are recorded without errors by a lenient histogram.
+    h.set_policy(RecordPolicy::Lenient);
+    h.record(f64::NAN)
+        .unwrap()
+        .record(1e200)
+        .unwrap()
+        .record(1e-200)
+        .unwrap();
+    h.record_f64s(1.0, i64::MAX)
+        .unwrap()
+        .record_f64s(1.0, i64::MAX)
+        .unwrap();
+    assert_eq!(h.count_nearby(f64::NAN), 2);
+    assert_eq!(h.count_nearby(0.0), 2);
+    assert_eq!(h.count_nearby(1.0), u64::MAX);
+}
+
+#[test]
+fn atomic_record_strict() {
+    use circllhist::{AtomicHistogram, RecordError, RecordPolicy};
+
+    let atomic = AtomicHistogram::with_policy(RecordPolicy::Strict);
+    atomic.record(1.0).unwrap().record_int_scale(-5, 0).unwrap();
+    assert_eq!(
+        atomic.record(f64::INFINITY).unwrap_err(),
+        RecordError::NotFinite(f64::INFINITY)
+    );
+    assert_eq!(
+        atomic.record(1e200).unwrap_err(),
+        RecordError::Overflow(1e200)
+    );
+    assert_eq!(
+        atomic.record(1e-200).unwrap_err(),
+        RecordError::Underflow(1e-200)
+    );
+    atomic.record_f64s(1.0, u64::MAX - 1).unwrap();
+    assert_eq!(
+        atomic.record_f64s(1.0, 1).unwrap_err(),
+        RecordError::CountSaturated {
+            bin: Bin::for_value(1.0)
+        }
+    );
+    assert_eq!(
+        atomic.record_f64s(1.0, 1).unwrap_err().to_string(),
+        "count of bin 1e0 would exceed the maximum count"
+    );
+
+    let snapshot = atomic.snapshot_and_reset();
+    assert_eq!(snapshot.policy(), RecordPolicy::Strict);
+    assert_eq!(snapshot.buckets().count(), 2);
+    assert_eq!(snapshot.count_nearby(1.0), u64::MAX);
+    assert_eq!(snapshot.count_nearby(-5.0), 1);
+}
+
+#[test]
+fn parse_errors() {
+    use circllhist::ParseErrorKind;
+
+    let error = Histogram::from_strs(["H[1.2e-01]=2", " H[1.3e-01]=-1"]).unwrap_err();
+    assert_eq!(error.kind(), &ParseErrorKind::NegCount);
+    assert_eq!(error.index(), 1);
+    assert_eq!(error.offset(), 12);
+    assert_eq!(error.token(), "H[1.3e-01]=-1");
+    assert_eq!(
+        error.to_string(),
+        "invalid bin 1 `H[1.3e-01]=-1` at byte 12: bin count must not be negative"
+    );
+
+    // each bin string, the kind of error it causes, and where.
+    let cases = [
+        ("=2", "NoBin", 0),
+        ("H[1.2e-01]", "NoCount", 10),
+        ("H[1.2e-01]=2=3", "Expected", 12),
+        ("[1.2e-01]=2", "Expected", 0),
+        ("H[1.2e-01=2", "Expected", 9),
+        ("H[lol]=2", "ParseBin", 2),
+        ("H[1.2e-01]= lol", "ParseCount", 12),
+    ];
+    for (bin, kind, offset) in cases {
+        let error = Histogram::from_strs([bin]).unwrap_err();
+        assert!(
+            format!("{:?}", error.kind()).starts_with(kind),
+            "{bin}: {error}"
+        );
+        assert_eq!(error.offset(), offset, "{bin}: {error}");
+        assert_eq!(error.token(), bin);
+    }
+
+    // offsets are relative to the whole string when parsing one string.
+    let error = "H[1.2e-01]=2, H[1.3e-01]=x\nH[2.2e-01]=1"
+        .parse::<Histogram>()
+        .unwrap_err();
+    assert!(matches!(error.kind(), ParseErrorKind::ParseCount(_)));
+    assert_eq!(error.index(), 1);
+    assert_eq!(error.offset(), 25);
+    assert_eq!(error.token(), "H[1.3e-01]=x");
+    let error: Box<dyn std::error::Error> = Box::new(error);
+    assert!(error.source().is_some());
+}
+
+#[test]
+fn parse_lenient() {
+    let (h, errors) =
+        Histogram::from_strs_lenient(["H[1.2e-01]=2", "H[1.2e-01]=-2", "H[1.3e-01]=1", "lol"]);
+    assert_eq!(
+        h,
+        Histogram::from_strs(["H[1.2e-01]=2", "H[1.3e-01]=1"]).unwrap()
+    );
+    let indices = errors.iter().map(|e| e.index()).collect::<Vec<_>>();
+    assert_eq!(indices, [1, 3]);
+
+    let (h, errors) = Histogram::from_str_lenient("H[1.2e-01]=2,H[lol]=1,H[1.3e-01]=1");
+    assert_eq!(
+        h,
+        Histogram::from_strs(["H[1.2e-01]=2", "H[1.3e-01]=1"]).unwrap()
+    );
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].index(), 1);
+    assert_eq!(errors[0].offset(), 15);
+
+    let (h, errors) = Histogram::from_strs_lenient(STRINGS);
+    assert_eq!(h, Histogram::from_strs(STRINGS).unwrap());
+    assert!(errors.is_empty());
+}
+
 // func TestCompare(t *testing.T) {
 // 	// var h1, h2 *Bin
 // }
@@ -195,6 +1176,86 @@ fn quantiles6() {
 // 	}
 // }
 
+#[test]
+fn concurrent() {
+    use circllhist::AtomicHistogram;
+    use std::{sync::Arc, thread};
+
+    let h = Arc::new(AtomicHistogram::new());
+    let threads = (0..16)
+        .map(|_| {
+            let h = h.clone();
+            thread::spawn(move || {
+                for _ in 0..100 {
+                    for i in 50..100 {
+                        h.record(i as f64)
+                            .expect("value should be recorded successfully");
+                    }
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    let mut expected = Histogram::default();
+    for i in 50..100 {
+        expected.record_f64s(i as f64, 16 * 100).unwrap();
+    }
+    assert_eq!(h.snapshot(), expected);
+}
+
+#[test]
+fn concurrent_saturation() {
+    use circllhist::AtomicHistogram;
+    use std::{sync::Arc, thread};
+
+    let h = Arc::new(AtomicHistogram::new());
+    let threads = (0..8)
+        .map(|_| {
+            let h = h.clone();
+            thread::spawn(move || {
+                for _ in 0..200_000 {
+                    h.record_f64s(1.0, u64::MAX / 4).unwrap();
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    // once the count saturates it must never be seen to wrap around.
+    let mut last = 0;
+    while !threads.iter().all(|thread| thread.is_finished()) {
+        let count = h.snapshot().count_nearby(1.0);
+        assert!(count >= last, "{count} < {last}");
+        last = count;
+    }
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    assert_eq!(h.snapshot().count_nearby(1.0), u64::MAX);
+}
+
+#[test]
+fn atomic_snapshot_and_reset() {
+    use circllhist::AtomicHistogram;
+
+    let atomic = AtomicHistogram::new();
+    let mut expected = Histogram::default();
+    for &sample in S1.iter().chain(&[f64::NAN, 1e200, -1.5e-3, 42.0, 1e-200]) {
+        atomic.record(sample).unwrap();
+        expected.record(sample).unwrap();
+    }
+    atomic.record_int_scales(12, -2, 5).unwrap();
+    expected.record_int_scales(12, -2, 5).unwrap();
+
+    assert_eq!(atomic.snapshot(), expected);
+    assert_eq!(atomic.snapshot_and_reset(), expected);
+    assert!(atomic.snapshot().is_empty());
+
+    atomic.record(1.0).unwrap();
+    assert_eq!(atomic.snapshot_and_reset().to_string(), "H[1.0e0]=1");
+}
+
 // func TestRang(t *testing.T) {
 // 	h1 := hist.New()
 // 	rnd := rand.New(rand.NewSource(time.Now().UnixNano()))
@@ -371,6 +1432,228 @@ fn merge() {
     // }
 }
 
+#[test]
+fn merge_interleaved() {
+    let mut h1 = Histogram::default();
+    let mut h2 = Histogram::default();
+    let mut expect = Histogram::default();
+    for exp in -5..5 {
+        for digits in 10..100 {
+            let val = digits as f64 * 10f64.powi(exp);
+            if digits % 3 == 0 {
+                h1.record(val).unwrap();
+            } else {
+                h2.record(val).unwrap();
+            }
+            expect.record(val).unwrap();
+        }
+    }
+    h1.record(f64::NAN).unwrap();
+    expect.record(f64::NAN).unwrap();
+
+    let mut merged = h1.clone();
+    merged.merge_from(&h2);
+    assert_eq!(merged, expect);
+
+    // merging in the other direction should produce the same histogram.
+    let mut merged = h2.clone();
+    merged.merge_from(&h1);
+    assert_eq!(merged, expect);
+
+    // merging with an empty histogram does nothing.
+    let mut empty = Histogram::default();
+    merged.merge_from(&empty);
+    assert_eq!(merged, expect);
+    empty.merge_from(&merged);
+    assert_eq!(empty, expect);
+}
+
+#[test]
+fn merge_saturates() {
+    let mut h1 = Histogram::default();
+    h1.record_f64s(1.0, i64::MAX).unwrap();
+    h1.record_f64s(1.0, i64::MAX).unwrap();
+    let mut h2 = Histogram::default();
+    h2.record_f64s(1.0, 10).unwrap();
+
+    h1.merge_from(&h2);
+    assert_eq!(h1.to_string(), format!("H[1.0e0]={}", u64::MAX));
+}
+
+#[test]
+fn merge_all() {
+    let histograms = (0..50)
+        .map(|i| {
+            let mut h = Histogram::default();
+            for j in 0..100 {
+                h.record_f64s((i * 7 + j * 13) as f64, i + 1).unwrap();
+            }
+            if i % 10 == 0 {
+                h.record(f64::NAN).unwrap();
+            }
+            h
+        })
+        .collect::<Vec<_>>();
+
+    let mut expect = Histogram::default();
+    for h in &histograms {
+        expect.merge_from(h);
+    }
+
+    assert_eq!(Histogram::merge_all(&histograms), expect);
+    assert_eq!(histograms.iter().collect::<Histogram>(), expect);
+    assert_eq!(histograms.iter().sum::<Histogram>(), expect);
+}
+
+#[test]
+fn merge_all_edge_cases() {
+    assert!(Histogram::merge_all(&[]).is_empty());
+
+    let h = Histogram::from_strs(STRINGS).unwrap();
+    let empty = Histogram::default();
+    assert_eq!(Histogram::merge_all([&empty, &h, &empty]), h);
+
+    let mut big = Histogram::default();
+    big.record_f64s(1.0, i64::MAX).unwrap();
+    big.record_f64s(1.0, i64::MAX).unwrap();
+    let merged = Histogram::merge_all([&big, &h, &big]);
+    assert_eq!(
+        merged.display_bins().last().unwrap().to_string(),
+        format!("H[1.0e0]={}", u64::MAX)
+    );
+}
+
+#[test]
+fn merge_all_policy() {
+    use circllhist::RecordPolicy;
+
+    let lenient = Histogram::from_strs(STRINGS).unwrap();
+    let mut strict = Histogram::with_policy(RecordPolicy::Strict);
+    assert_eq!(
+        Histogram::merge_all([&lenient, &lenient]).policy(),
+        RecordPolicy::Lenient
+    );
+    // the strict histogram is empty, but its policy is kept.
+    for histograms in [[&lenient, &strict], [&strict, &lenient]] {
+        assert_eq!(
+            Histogram::merge_all(histograms).policy(),
+            RecordPolicy::Strict
+        );
+        assert_eq!(
+            histograms.into_iter().sum::<Histogram>().policy(),
+            RecordPolicy::Strict
+        );
+    }
+    strict.record(1.0).unwrap();
+    assert_eq!(
+        Histogram::merge_all([&strict]).policy(),
+        RecordPolicy::Strict
+    );
+    assert_eq!(
+        Histogram::merge_all([&strict, &strict]).policy(),
+        RecordPolicy::Strict
+    );
+}
+
+#[test]
+fn subtract() {
+    let mut earlier = Histogram::default();
+    let mut later = Histogram::default();
+    let mut expect = Histogram::default();
+    for i in 0..100 {
+        let i = i as f64;
+        earlier.record_f64s(i, 2).unwrap();
+        later.record_f64s(i, 2).unwrap();
+    }
+    // values recorded after the earlier snapshot was taken.
+    for i in 50..150 {
+        let i = i as f64;
+        later.record(i).unwrap();
+        expect.record(i).unwrap();
+    }
+    later.record(f64::NAN).unwrap();
+    expect.record(f64::NAN).unwrap();
+
+    let delta = later.delta(&earlier).expect("delta should succeed");
+    assert_eq!(delta, expect);
+
+    later
+        .subtract(&earlier)
+        .expect("subtraction should succeed");
+    assert_eq!(later, expect);
+
+    // subtracting everything leaves no buckets behind.
+    later.subtract(&expect).expect("subtraction should succeed");
+    assert!(later.is_empty(), "{later:#}");
+}
+
+#[test]
+fn subtract_underflow() {
+    use circllhist::SubtractError;
+
+    let mut h = Histogram::from_strs(["H[1.0e+00]=3", "H[2.0e+00]=1"]).unwrap();
+    let original = h.clone();
+
+    // more values than are present in the bin
+    let err = h
+        .subtract(&Histogram::from_strs(["H[1.0e+00]=4"]).unwrap())
+        .unwrap_err();
+    assert_eq!(
+        err,
+        SubtractError::Underflow {
+            bin: Bin::for_value(1.0),
+            available: 3,
+            subtrahend: 4
+        }
+    );
+    assert_eq!(h, original, "histogram should be unchanged");
+
+    // a bin that isn't present, less than every bin in `h`...
+    let err = h
+        .delta(&Histogram::from_strs(["H[5.0e-01]=1", "H[1.0e+00]=1"]).unwrap())
+        .unwrap_err();
+    assert_eq!(
+        err,
+        SubtractError::Underflow {
+            bin: Bin::for_value(0.5),
+            available: 0,
+            subtrahend: 1
+        }
+    );
+
+    // ...between bins in `h`...
+    let err = h
+        .delta(&Histogram::from_strs(["H[1.5e+00]=1"]).unwrap())
+        .unwrap_err();
+    assert_eq!(
+        err,
+        SubtractError::Underflow {
+            bin: Bin::for_value(1.5),
+            available: 0,
+            subtrahend: 1
+        }
+    );
+
+    // ...and greater than every bin in `h`.
+    let err = h
+        .subtract(&Histogram::from_strs(["H[1.0e+00]=1", "H[3.0e+00]=1"]).unwrap())
+        .unwrap_err();
+    assert_eq!(
+        err,
+        SubtractError::Underflow {
+            bin: Bin::for_value(3.0),
+            available: 0,
+            subtrahend: 1
+        }
+    );
+    assert_eq!(
+        err.to_string(),
+        "cannot subtract 1 values from bin 3e0, which has 0 values"
+    );
+    assert_eq!(h, original, "histogram should be unchanged");
+}
+
+// see `benches/merge.rs` for a port of `BenchmarkHistogramMerge`.
 // func BenchmarkHistogramMerge(b *testing.B) {
 // 	b.Run("random", func(b *testing.B) {
 // 		rand.New(rand.NewSource(time.Now().UnixNano()))
@@ -0,0 +1,112 @@
+use circllhist::{Bin, HdrHistogramError, Histogram};
+
+fn new_hdr(values: &[(u64, u64)]) -> hdrhistogram::Histogram<u64> {
+    let mut hdr = hdrhistogram::Histogram::new(3).unwrap();
+    for &(value, count) in values {
+        hdr.record_n(value, count).unwrap();
+    }
+    hdr
+}
+
+#[test]
+fn from_hdrhistogram() {
+    let hdr = new_hdr(&[(0, 1), (5, 2), (150, 1), (1234, 1), (123_456, 1)]);
+    let h = Histogram::from(&hdr);
+    assert_eq!(
+        h.dec_strings(),
+        [
+            "H[0.0e+00]=1",
+            "H[5.0e+00]=2",
+            "H[1.5e+02]=1",
+            "H[1.2e+03]=1",
+            "H[1.2e+05]=1",
+        ]
+    );
+
+    // microseconds, as seconds.
+    let h = Histogram::from_hdrhistogram(&hdr, -6);
+    assert_eq!(
+        h.dec_strings(),
+        [
+            "H[0.0e+00]=1",
+            "H[5.0e-06]=2",
+            "H[1.5e-04]=1",
+            "H[1.2e-03]=1",
+            "H[1.2e-01]=1",
+        ]
+    );
+
+    // counts larger than `i64::MAX` aren't clamped.
+    let mut hdr = new_hdr(&[(7, u64::MAX - 1)]);
+    hdr.record(7).unwrap();
+    let h = Histogram::from(&hdr);
+    assert_eq!(h.dec_strings(), [format!("H[7.0e+00]={}", u64::MAX)]);
+}
+
+#[test]
+fn to_hdrhistogram() {
+    let mut h = Histogram::new();
+    for val in [0.0, 5.0, 5.0, 150.0, 1234.0] {
+        h.record(val).unwrap();
+    }
+    let hdr = hdrhistogram::Histogram::try_from(&h).unwrap();
+    assert_eq!(hdr.len(), 5);
+    // the bins' midpoints, rounded.
+    for (value, count) in [(0, 1), (5, 2), (155, 1), (1250, 1)] {
+        assert_eq!(hdr.count_at(value), count, "{value}");
+    }
+
+    let mut h = Histogram::new();
+    for val in [0.123, 2.5] {
+        h.record(val).unwrap();
+    }
+    let hdr = h.to_hdrhistogram(-3).unwrap();
+    assert_eq!(hdr.len(), 2);
+    assert_eq!(hdr.count_at(125), 1);
+    assert_eq!(hdr.count_at(2550), 1);
+}
+
+#[test]
+fn to_hdrhistogram_errors() {
+    let mut h = Histogram::new();
+    h.record(-1.0).unwrap().record(1.0).unwrap();
+    assert_eq!(
+        h.to_hdrhistogram(0),
+        Err(HdrHistogramError::Negative {
+            bin: Bin::for_value(-1.0)
+        })
+    );
+    assert_eq!(
+        h.to_hdrhistogram(0).unwrap_err().to_string(),
+        "HdrHistogram can't record negative values (bin -1e0)"
+    );
+    h.record(f64::NAN).unwrap();
+    assert_eq!(h.to_hdrhistogram(0), Err(HdrHistogramError::NotANumber));
+
+    let mut h = Histogram::new();
+    h.record(1e20).unwrap();
+    assert_eq!(
+        h.to_hdrhistogram(0),
+        Err(HdrHistogramError::Overflow {
+            bin: Bin::for_value(1e20)
+        })
+    );
+    assert!(h.to_hdrhistogram(3).is_ok());
+}
+
+#[test]
+fn hdrhistogram_roundtrip() {
+    let mut hdr = hdrhistogram::Histogram::<u64>::new(3).unwrap();
+    for value in 1..=10_000 {
+        hdr.record(value).unwrap();
+    }
+    let h = Histogram::from(&hdr);
+    assert_eq!(h.approx_count_below(f64::INFINITY), 10_000);
+    let roundtrip = hdrhistogram::Histogram::try_from(&h).unwrap();
+    assert_eq!(roundtrip.len(), 10_000);
+    for q in [0.01, 0.1, 0.25, 0.5, 0.75, 0.9, 0.99, 1.0] {
+        let (a, b) = (hdr.value_at_quantile(q), roundtrip.value_at_quantile(q));
+        // values move to the midpoint of their bin, at most 5% away.
+        assert!(a.abs_diff(b) as f64 <= a as f64 * 0.05 + 1.0, "{a} != {b}");
+    }
+}
@@ -0,0 +1,75 @@
+use circllhist::Histogram;
+
+#[test]
+fn json() {
+    let mut h = Histogram::default();
+    for val in [f64::NAN, -1.0, 0.12, 0.12, 5.5e20] {
+        h.record(val).unwrap();
+    }
+    let json = h.to_json();
+    assert_eq!(
+        json,
+        r#"{"_type":"h","_value":["H[NaN]=1","H[-1.0e+00]=1","H[1.2e-01]=2","H[5.5e+20]=1"]}"#
+    );
+    assert_eq!(Histogram::from_json(&json).unwrap(), h);
+    assert_eq!(Histogram::new().to_json(), r#"{"_type":"h","_value":[]}"#);
+
+    // IRONdb fetch responses, with whitespace, extra fields and escapes.
+    let doc = r#" {
+        "_value" : [ "H[1.2e-01]=1", "H[1.2e-01]=1" ],
+        "_ts": 1.5e3, "tags": {"a": [true, false, null, "\"x\""]},
+        "_type": "h"
+    } "#;
+    let expected = Histogram::from_strs(["H[1.2e-01]=2"]).unwrap();
+    assert_eq!(Histogram::from_json(doc).unwrap(), expected);
+    assert_eq!(
+        Histogram::from_json(r#"["H[1.2e-01]=1","H[1.2e-01]=1"]"#).unwrap(),
+        expected
+    );
+    assert_eq!(Histogram::from_json("[]").unwrap(), Histogram::new());
+}
+
+#[test]
+fn json_errors() {
+    use circllhist::JsonError;
+
+    let syntax = |json: &str| match Histogram::from_json(json) {
+        Err(JsonError::Syntax { offset, .. }) => offset,
+        res => panic!("expected a syntax error for {json:?}, got {res:?}"),
+    };
+    assert_eq!(syntax(""), 0);
+    assert_eq!(syntax(r#""H[1.2e-01]=1""#), 0);
+    assert_eq!(syntax(r#"["H[1.2e-01]=1""#), 15);
+    assert_eq!(syntax(r#"["H[1.2e-01]=1",]"#), 16);
+    assert_eq!(syntax(r#"["H[1.2e-01]=1"] x"#), 17);
+    assert_eq!(syntax(r#"[1]"#), 1);
+    assert_eq!(syntax(r#"{"_type":"h","_value":[],"x":01}"#), 30);
+    assert_eq!(syntax(r#"{"_type":"h","_value":[],"_value":[]}"#), 34);
+    assert_eq!(syntax(r#"["\ud800"]"#), 8);
+    assert_eq!(syntax(&format!("{{\"x\":{}}}", "[".repeat(100))), 70);
+
+    assert_eq!(
+        Histogram::from_json(r#"{"_type":"n","_value":[]}"#),
+        Err(JsonError::NotHistogram {
+            offset: 10,
+            found: "n".to_string()
+        })
+    );
+    assert_eq!(
+        Histogram::from_json(r#"{"_value":[]}"#),
+        Err(JsonError::MissingType)
+    );
+    assert_eq!(
+        Histogram::from_json(r#"{"_type":"h"}"#),
+        Err(JsonError::MissingValue)
+    );
+
+    match Histogram::from_json(r#"{"_type":"h","_value":["H[1.2e-01]=1", "H[1.2e-01]=x"]}"#) {
+        Err(JsonError::Bin(error)) => {
+            assert_eq!(error.index(), 1);
+            assert_eq!(error.token(), "H[1.2e-01]=x");
+            assert_eq!(error.offset(), 51);
+        }
+        res => panic!("expected a bin error, got {res:?}"),
+    }
+}
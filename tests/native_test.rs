@@ -0,0 +1,179 @@
+use circllhist::Histogram;
+mod util;
+
+#[test]
+fn native_histogram() {
+    use circllhist::{BucketDistribution, BucketSpan, NativeHistogram};
+
+    let mut h = Histogram::default();
+    for val in [0.0, 1e-40, 1.0, 1.5, -3.0, f64::NAN] {
+        h.record(val).unwrap();
+    }
+    // at schema 5, 1.05 is in bucket 3, (2^(2/32), 2^(3/32)], 1.55 in bucket
+    // 21 and -3.05 in bucket 52.
+    let native = h.to_native_histogram(5);
+    assert_eq!(native.schema, 5);
+    assert_eq!(native.count, 6);
+    assert!(native.sum.is_nan());
+    // 1e-40 is within the default zero threshold.
+    assert_eq!(native.zero_count, 2);
+    assert_eq!(native.zero_threshold, 2f64.powi(-128));
+    assert_eq!(NativeHistogram::DEFAULT_ZERO_THRESHOLD, 2f64.powi(-128));
+    assert_eq!(
+        native.positive_spans,
+        [
+            BucketSpan {
+                offset: 3,
+                length: 1
+            },
+            BucketSpan {
+                offset: 17,
+                length: 1
+            }
+        ]
+    );
+    assert_eq!(native.positive_deltas, [1, 0]);
+    assert_eq!(
+        native.negative_spans,
+        [BucketSpan {
+            offset: 52,
+            length: 1
+        }]
+    );
+    assert_eq!(native.negative_deltas, [1]);
+    assert_eq!(
+        Histogram::from_native_histogram(&native, BucketDistribution::Midpoint).unwrap(),
+        Histogram::from_strs([
+            "H[0.0e+00]=2",
+            "H[1.0e+00]=1",
+            "H[1.5e+00]=1",
+            "H[-3.0e+00]=1"
+        ])
+        .unwrap()
+    );
+
+    // consecutive buckets share a span, and counts are delta-encoded across
+    // spans.
+    let h = Histogram::from_strs([
+        "H[1.0e+00]=3",
+        "H[1.1e+00]=5",
+        "H[1.2e+00]=2",
+        "H[4.0e+00]=7",
+    ])
+    .unwrap();
+    let native = h.to_native_histogram(3);
+    // 1.05, 1.15 and 1.25 are in buckets 1, 2 and 3, (2^((i - 1)/8), 2^(i/8)],
+    // and 4.05 in bucket 17.
+    assert_eq!(
+        native.positive_spans,
+        [
+            BucketSpan {
+                offset: 1,
+                length: 3
+            },
+            BucketSpan {
+                offset: 13,
+                length: 1
+            }
+        ]
+    );
+    assert_eq!(native.positive_deltas, [3, 2, -3, 5]);
+    assert!(native.negative_spans.is_empty());
+    assert_eq!(native.count, 17);
+    assert_approx_eq!(native.sum, h.approx_sum());
+
+    let roundtrip =
+        Histogram::from_native_histogram(&native, BucketDistribution::Midpoint).unwrap();
+    assert_eq!(roundtrip.approx_count_below(2.0), 10);
+    assert_eq!(roundtrip.approx_count_above(2.0), 7);
+
+    let native = NativeHistogram {
+        schema: -4,
+        positive_spans: vec![BucketSpan {
+            offset: 0,
+            length: 1,
+        }],
+        positive_deltas: vec![4],
+        ..NativeHistogram::default()
+    };
+    // bucket 0 is (2^-16, 1].
+    let h = Histogram::from_native_histogram(&native, BucketDistribution::UpperBound).unwrap();
+    assert_eq!(h, Histogram::from_strs(["H[9.9e-01]=4"]).unwrap());
+
+    // the zero bucket's values are recorded in the zero bin, whatever its
+    // threshold and the distribution.
+    let native = NativeHistogram {
+        zero_threshold: 1e-3,
+        zero_count: 4,
+        ..NativeHistogram::default()
+    };
+    for distribution in [
+        BucketDistribution::Uniform,
+        BucketDistribution::Midpoint,
+        BucketDistribution::UpperBound,
+    ] {
+        let h = Histogram::from_native_histogram(&native, distribution).unwrap();
+        assert_eq!(h, Histogram::from_strs(["H[0.0e+00]=4"]).unwrap());
+    }
+}
+
+#[test]
+fn native_histogram_errors() {
+    use circllhist::{BucketDistribution, BucketSpan, NativeHistogram, NativeHistogramError};
+
+    let span = |offset, length| BucketSpan { offset, length };
+    let err = |schema, spans: &[BucketSpan], deltas: &[i64]| {
+        let native = NativeHistogram {
+            schema,
+            negative_spans: spans.to_vec(),
+            negative_deltas: deltas.to_vec(),
+            ..NativeHistogram::default()
+        };
+        Histogram::from_native_histogram(&native, BucketDistribution::Uniform).unwrap_err()
+    };
+    assert_eq!(err(9, &[], &[]), NativeHistogramError::InvalidSchema(9));
+    assert_eq!(err(-5, &[], &[]), NativeHistogramError::InvalidSchema(-5));
+    assert_eq!(
+        err(0, &[span(0, 2), span(3, 1)], &[1, 1]),
+        NativeHistogramError::SpanMismatch {
+            spans: 3,
+            deltas: 2
+        }
+    );
+    assert_eq!(
+        err(0, &[span(-2, 2), span(3, 1)], &[1, 1, -3]),
+        NativeHistogramError::InvalidCount { index: 3 }
+    );
+    assert_eq!(
+        err(0, &[span(0, 1)], &[-1]),
+        NativeHistogramError::InvalidCount { index: 0 }
+    );
+    assert_eq!(
+        err(8, &[span(0, 1), span(i32::MAX, 1)], &[1, 0]),
+        NativeHistogramError::InvalidIndex {
+            index: i64::from(i32::MAX) + 1
+        }
+    );
+    // only the first span's offset may be negative.
+    assert_eq!(
+        err(0, &[span(0, 1), span(-5, 1)], &[1, 0]),
+        NativeHistogramError::InvalidIndex { index: -4 }
+    );
+    // every bucket's index is checked, not just the first and last.
+    assert_eq!(
+        err(
+            0,
+            &[span(0, 1), span(i32::MAX, 1), span(i32::MIN, 1)],
+            &[1, 0, 0]
+        ),
+        NativeHistogramError::InvalidIndex {
+            index: i64::from(i32::MAX) + 1
+        }
+    );
+}
+
+#[test]
+#[should_panic(expected = "invalid native histogram schema")]
+fn native_histogram_invalid_schema() {
+    let _ = Histogram::new().to_native_histogram(9);
+}
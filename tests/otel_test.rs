@@ -0,0 +1,209 @@
+use circllhist::Histogram;
+mod util;
+
+#[test]
+fn exponential_histogram() {
+    use circllhist::{BucketDistribution, ExponentialBuckets, ExponentialHistogramDataPoint};
+
+    let mut h = Histogram::default();
+    for val in [0.0, 0.0, 1.0, 1.5, -3.0, f64::NAN] {
+        h.record(val).unwrap();
+    }
+    // the narrowest bin, [3.0, 3.1), is 3.3% wide, so scale 5's 2.2% wide
+    // buckets are needed.
+    let point = h.to_exponential_histogram();
+    assert_eq!(point.scale, 5);
+    assert_eq!(point.count, 5);
+    assert_eq!(point.zero_count, 2);
+    assert_eq!(point.zero_threshold, 0.0);
+    assert_approx_eq!(point.sum.unwrap(), -0.45);
+    // 1.05 is in bucket 2, (2^(2/32), 2^(3/32)], and 1.55 in bucket 20.
+    let mut positive = vec![0; 19];
+    positive[0] = 1;
+    positive[18] = 1;
+    assert_eq!(
+        point.positive,
+        ExponentialBuckets {
+            offset: 2,
+            bucket_counts: positive
+        }
+    );
+    assert_eq!(
+        point.negative,
+        ExponentialBuckets {
+            offset: 51,
+            bucket_counts: vec![1]
+        }
+    );
+    assert_eq!(ExponentialHistogramDataPoint::from(&h), point);
+
+    // each of those buckets is within a single bin.
+    let expected = Histogram::from_strs([
+        "H[0.0e+00]=2",
+        "H[1.0e+00]=1",
+        "H[1.5e+00]=1",
+        "H[-3.0e+00]=1",
+    ])
+    .unwrap();
+    assert_eq!(Histogram::try_from(&point).unwrap(), expected);
+
+    // buckets at smaller scales span many bins.
+    let point = ExponentialHistogramDataPoint {
+        scale: 0,
+        count: 4,
+        positive: ExponentialBuckets {
+            offset: 0,
+            bucket_counts: vec![4],
+        },
+        ..ExponentialHistogramDataPoint::default()
+    };
+    assert_eq!(
+        Histogram::from_exponential_histogram(&point, BucketDistribution::Uniform).unwrap(),
+        Histogram::from_strs([
+            "H[1.1e+00]=1",
+            "H[1.3e+00]=1",
+            "H[1.6e+00]=1",
+            "H[1.8e+00]=1"
+        ])
+        .unwrap()
+    );
+    assert_eq!(
+        Histogram::from_exponential_histogram(&point, BucketDistribution::Midpoint).unwrap(),
+        Histogram::from_strs(["H[1.5e+00]=4"]).unwrap()
+    );
+
+    let point = Histogram::new().to_exponential_histogram_with_scale(-10);
+    assert_eq!(point.count, 0);
+    assert_eq!(point.positive, ExponentialBuckets::default());
+    assert_eq!(Histogram::try_from(&point).unwrap(), Histogram::new());
+}
+
+#[test]
+fn exponential_histogram_roundtrip() {
+    use circllhist::ExponentialHistogramDataPoint;
+
+    let mut h = Histogram::default();
+    for i in 1..10_000 {
+        let val = f64::from(i) * 0.037;
+        h.record(val).unwrap();
+        h.record(-val / 1000.0).unwrap();
+    }
+    // the values of each sign span about 13 powers of 2, so the default 160
+    // buckets are enough at scale 3, with 8 buckets per power of 2, and 1,800
+    // at scale 7.
+    let point = h.to_exponential_histogram();
+    assert_eq!(point.scale, 3);
+    let point =
+        h.to_exponential_histogram_with_max_size(ExponentialHistogramDataPoint::MAX_SCALE, 1_800);
+    assert_eq!(point.scale, 7);
+    let roundtrip = Histogram::try_from(&point).unwrap();
+    assert_eq!(roundtrip.approx_count_below(f64::INFINITY), 19_998);
+    assert_approx_eq!(roundtrip.approx_mean(), h.approx_mean());
+    let qs = [0.01, 0.1, 0.25, 0.5, 0.75, 0.9, 0.99];
+    for (a, b) in h
+        .approx_quantiles(&qs)
+        .unwrap()
+        .iter()
+        .zip(roundtrip.approx_quantiles(&qs).unwrap())
+    {
+        // values stay within one bin of where they were.
+        assert!((a - b).abs() <= a.abs() * 0.1, "{a} != {b}");
+    }
+}
+
+#[test]
+fn exponential_histogram_errors() {
+    use circllhist::{ExponentialHistogramDataPoint, ExponentialHistogramError};
+
+    let point = ExponentialHistogramDataPoint {
+        scale: 21,
+        ..ExponentialHistogramDataPoint::default()
+    };
+    assert_eq!(
+        Histogram::try_from(&point),
+        Err(ExponentialHistogramError::InvalidScale(21))
+    );
+}
+
+#[test]
+#[should_panic(expected = "invalid exponential histogram scale")]
+fn exponential_histogram_invalid_scale() {
+    let _ = Histogram::new().to_exponential_histogram_with_scale(-11);
+}
+
+#[test]
+fn exponential_histogram_otlp_json() {
+    use circllhist::{ExponentialBuckets, ExponentialHistogramDataPoint};
+
+    let point = ExponentialHistogramDataPoint {
+        start_time_unix_nano: 1_700_000_000_000_000_000,
+        time_unix_nano: 1_700_000_060_000_000_000,
+        count: 6,
+        sum: Some(12.5),
+        scale: 3,
+        zero_count: 1,
+        positive: ExponentialBuckets {
+            offset: -2,
+            bucket_counts: vec![2, 0, 3],
+        },
+        negative: ExponentialBuckets::default(),
+        min: None,
+        max: Some(f64::INFINITY),
+        zero_threshold: 1e-300,
+    };
+    assert_eq!(
+        point.to_otlp_json(),
+        concat!(
+            r#"{"startTimeUnixNano":"1700000000000000000","timeUnixNano":"1700000060000000000","#,
+            r#""count":"6","sum":12.5,"scale":3,"zeroCount":"1","#,
+            r#""positive":{"offset":-2,"bucketCounts":["2","0","3"]},"#,
+            r#""negative":{"offset":0,"bucketCounts":[]},"max":"Infinity","zeroThreshold":1e-300}"#
+        )
+    );
+    let json: serde_json::Value = serde_json::from_str(&point.to_otlp_json()).unwrap();
+    assert_eq!(json["positive"]["bucketCounts"][2], "3");
+}
+
+#[test]
+fn exponential_histogram_max_size() {
+    // at scale 20, the buckets between 1.0 and 1e3 alone would number over ten
+    // million.
+    let mut h = Histogram::default();
+    for val in [1e-100, 1.0, 1e3, 1e100, -1e-9, -1e9] {
+        h.record(val).unwrap();
+    }
+    for point in [
+        h.to_exponential_histogram(),
+        h.to_exponential_histogram_with_scale(20),
+    ] {
+        assert_eq!(point.scale, -3);
+        assert_eq!(point.count, 6);
+        assert!(point.positive.bucket_counts.len() <= 160);
+        assert!(point.negative.bucket_counts.len() <= 160);
+        assert!(
+            point.positive.bucket_counts.len() > 80 || point.negative.bucket_counts.len() > 80,
+            "the scale is no smaller than needed"
+        );
+    }
+
+    let point = h.to_exponential_histogram_with_max_size(20, 2);
+    assert_eq!(point.scale, -9);
+    assert_eq!(point.positive.bucket_counts, [1, 3]);
+    assert_eq!(point.negative.bucket_counts, [1, 1]);
+
+    // values in a single bin fit at any scale, and 1.05 and 1.55, about half
+    // a power of 2 apart, need 144 buckets at scale 8.
+    let mut h = Histogram::default();
+    h.record(1.0).unwrap();
+    assert_eq!(h.to_exponential_histogram_with_scale(20).scale, 20);
+    h.record(1.5).unwrap();
+    let point = h.to_exponential_histogram_with_scale(20);
+    assert_eq!(point.scale, 8);
+    assert_eq!(point.positive.bucket_counts.len(), 144);
+}
+
+#[test]
+#[should_panic(expected = "exponential histogram max size must be at least 2")]
+fn exponential_histogram_invalid_max_size() {
+    let _ = Histogram::new().to_exponential_histogram_with_max_size(0, 1);
+}
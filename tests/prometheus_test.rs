@@ -0,0 +1,324 @@
+use circllhist::Histogram;
+mod util;
+
+#[test]
+fn prometheus() {
+    use circllhist::PrometheusBuckets;
+
+    let mut h = Histogram::default();
+    for val in [-2.0, 0.0, 0.12, 0.12, 0.5, 1.5e-7, 3.3e6, f64::NAN] {
+        h.record(val).unwrap();
+    }
+
+    let labels = [("path", "/a\"b\\c\nd"), ("code", "200")];
+    let text = h
+        .prometheus("http_latency:seconds", &labels, PrometheusBuckets::Bins)
+        .unwrap()
+        .to_string();
+    let buckets = util::assert_prometheus_histogram(&text, "http_latency:seconds");
+    assert_eq!(
+        buckets,
+        [
+            (-2.0, 1),
+            (0.0, 2),
+            (1.6e-7, 3),
+            (0.13, 5),
+            (0.51, 6),
+            (3.4e6, 7),
+            (f64::INFINITY, 8)
+        ]
+    );
+    assert!(text.contains(
+        "http_latency:seconds_bucket{path=\"/a\\\"b\\\\c\\nd\",code=\"200\",le=\"1.6e-07\"} 3\n"
+    ));
+    assert!(text.contains("le=\"3.4e+06\"} 7\n"));
+    // the NaN bin makes the sum NaN.
+    assert!(text.contains("http_latency:seconds_sum{path=\"/a\\\"b\\\\c\\nd\",code=\"200\"} NaN\n"));
+
+    let mut h = Histogram::default();
+    for val in [0.003, 0.02, 0.02, 0.4, 7.0, 150.0] {
+        h.record(val).unwrap();
+    }
+    let bounds = [0.005, 0.01, 0.1, 0.4, 1.0, 10.0];
+    let text = h
+        .prometheus("latency", &[], PrometheusBuckets::Bounds(&bounds))
+        .unwrap()
+        .to_string();
+    let buckets = util::assert_prometheus_histogram(&text, "latency");
+    assert_eq!(
+        buckets,
+        [
+            (0.005, 1),
+            (0.01, 1),
+            (0.1, 3),
+            // 0.4 is in the bin [0.4, 0.41), which isn't entirely below 0.4.
+            (0.4, 3),
+            (1.0, 4),
+            (10.0, 5),
+            (f64::INFINITY, 6)
+        ]
+    );
+    assert!(text.contains("\nlatency_bucket{le=\"0.005\"} 1\n"));
+    assert!(text.ends_with("\nlatency_count 6\n"));
+    let sum: f64 = text
+        .lines()
+        .find_map(|line| line.strip_prefix("latency_sum "))
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert_approx_eq!(sum, h.approx_sum());
+
+    // an explicit `+Inf` boundary isn't repeated.
+    let text = h
+        .prometheus(
+            "latency",
+            &[],
+            PrometheusBuckets::Bounds(&[1.0, f64::INFINITY]),
+        )
+        .unwrap()
+        .to_string();
+    assert_eq!(
+        util::assert_prometheus_histogram(&text, "latency"),
+        [(1.0, 4), (f64::INFINITY, 6)]
+    );
+
+    let text = Histogram::new()
+        .prometheus("empty", &[("a", "")], PrometheusBuckets::Bins)
+        .unwrap()
+        .to_string();
+    assert_eq!(
+        text,
+        "# TYPE empty histogram\n\
+         empty_bucket{a=\"\",le=\"+Inf\"} 0\n\
+         empty_sum{a=\"\"} 0\n\
+         empty_count{a=\"\"} 0\n"
+    );
+}
+
+#[test]
+fn prometheus_errors() {
+    use circllhist::{PrometheusBuckets, PrometheusError};
+
+    let h = Histogram::new();
+    let err = |name, labels: &[(&str, &str)], bounds: &[f64]| {
+        h.prometheus(name, labels, PrometheusBuckets::Bounds(bounds))
+            .unwrap_err()
+    };
+    assert_eq!(
+        err("1abc", &[], &[]),
+        PrometheusError::InvalidMetricName("1abc".to_string())
+    );
+    assert_eq!(
+        err("a-b", &[], &[]),
+        PrometheusError::InvalidMetricName("a-b".to_string())
+    );
+    for label in ["le", "__name__", "a:b", ""] {
+        assert_eq!(
+            err("abc", &[(label, "x")], &[]),
+            PrometheusError::InvalidLabelName(label.to_string())
+        );
+    }
+    assert_eq!(
+        err("abc", &[("a", "x"), ("b", "y"), ("a", "z")], &[]),
+        PrometheusError::DuplicateLabel("a".to_string())
+    );
+    assert_eq!(
+        err("abc", &[], &[1.0, 1.0]),
+        PrometheusError::InvalidBound {
+            index: 1,
+            bound: 1.0
+        }
+    );
+    assert_eq!(
+        err("abc", &[], &[1.0, f64::INFINITY, 2.0]),
+        PrometheusError::InvalidBound {
+            index: 2,
+            bound: 2.0
+        }
+    );
+    assert!(matches!(
+        err("abc", &[], &[f64::NAN]),
+        PrometheusError::InvalidBound { index: 0, .. }
+    ));
+}
+
+#[test]
+fn from_prometheus() {
+    use circllhist::{BucketDistribution, PrometheusBuckets};
+
+    // exposing a histogram's own bins and importing them again restores it.
+    let mut h = Histogram::default();
+    for val in [-2.0, -0.5, 0.0, 0.0, 0.12, 0.12, 0.5, 1.5e-7, 3.3e6] {
+        h.record(val).unwrap();
+    }
+    let text = h
+        .prometheus("latency", &[("a", "b")], PrometheusBuckets::Bins)
+        .unwrap()
+        .to_string();
+    let import =
+        Histogram::from_prometheus(&text, "latency", &[], BucketDistribution::UpperBound).unwrap();
+    assert_eq!(*import.histogram(), h);
+    assert_eq!(import.count(), Some(9));
+    assert_approx_eq!(import.sum().unwrap(), h.approx_sum());
+
+    // NaN values are exposed in the `+Inf` bucket, with a NaN sum.
+    h.record(f64::NAN).unwrap();
+    let text = h
+        .prometheus("latency", &[], PrometheusBuckets::Bins)
+        .unwrap()
+        .to_string();
+    let import =
+        Histogram::from_prometheus(&text, "latency", &[], BucketDistribution::UpperBound).unwrap();
+    assert_eq!(import.histogram().count_nearby(3.4e6), 1);
+    assert_eq!(import.count(), Some(10));
+    assert!(import.sum().unwrap().is_nan());
+
+    let text = r#"
+# HELP rpc_seconds RPC latency.
+# TYPE rpc_seconds histogram
+rpc_seconds_bucket{le="0.1",service="a"} 0
+rpc_seconds_bucket{le="0.2",service="a"} 10 1700000000000
+rpc_seconds_bucket{service="a",le="0.25"} 13
+rpc_seconds_bucket{service="a", le="+Inf",} 13
+rpc_seconds_sum{service="a"} 2.0
+rpc_seconds_count{service="a"} 13
+rpc_seconds_bucket{le="1.0",service="b"} 1 # {trace_id="x"} 0.5
+rpc_seconds_bucket{le="+Inf",service="b"} 1
+rpc_seconds_sum{service="b"} 0.5
+rpc_seconds_count{service="b"} 1
+rpc_seconds_created{service="b"} 1700000000
+rpc_seconds_total_bucket{le="+Inf"} 99
+other_bucket{le="+Inf"} 99
+# EOF
+"#;
+    let import = Histogram::from_prometheus(
+        text,
+        "rpc_seconds",
+        &[("service", "a")],
+        BucketDistribution::Uniform,
+    )
+    .unwrap();
+    // the 10 values in (0.1, 0.2] are spread over its 10 bins, and the 3
+    // values in (0.2, 0.25] over its 5 bins.
+    let mut expected = (10..20)
+        .map(|val| format!("H[{}.{}e-01]=1", val / 10, val % 10))
+        .collect::<Vec<_>>();
+    expected.extend(["H[2.0e-01]=1", "H[2.2e-01]=1", "H[2.4e-01]=1"].map(String::from));
+    assert_eq!(*import.histogram(), Histogram::from_strs(expected).unwrap());
+    assert_eq!(import.sum(), Some(2.0));
+    assert_eq!(import.count(), Some(13));
+    let losses = import
+        .precision_loss()
+        .iter()
+        .map(|loss| (loss.lower(), loss.upper(), loss.count()))
+        .collect::<Vec<_>>();
+    assert_eq!(losses, [(0.1, 0.2, 10), (0.2, 0.25, 3)]);
+
+    let import =
+        Histogram::from_prometheus(text, "rpc_seconds", &[], BucketDistribution::Midpoint).unwrap();
+    assert_eq!(
+        *import.histogram(),
+        Histogram::from_strs([
+            "H[1.5e-01]=10",
+            "H[2.2e-01]=3",
+            // the first bucket is assumed to start at zero.
+            "H[5.0e-01]=1"
+        ])
+        .unwrap()
+    );
+    assert_eq!(import.sum(), Some(2.5));
+    assert_eq!(import.count(), Some(14));
+    assert_eq!(import.precision_loss().len(), 3);
+
+    // the 2 values in (0, 1.1] are spread evenly over it, values in the `+Inf`
+    // bucket are put in the bin of the `le` before it, and a bucket that fits
+    // in one bin loses no precision.
+    let text = "x_bucket{le=\"1.1\"} 2\nx_bucket{le=\"1.2\"} 3\nx_bucket{le=\"+Inf\"} 5\n";
+    let import = Histogram::from_prometheus(text, "x", &[], BucketDistribution::Uniform).unwrap();
+    assert_eq!(
+        *import.histogram(),
+        Histogram::from_strs([
+            "H[2.7e-01]=1",
+            "H[8.2e-01]=1",
+            "H[1.1e+00]=1",
+            "H[1.2e+00]=2"
+        ])
+        .unwrap()
+    );
+    assert_eq!(import.sum(), None);
+    assert_eq!(import.count(), None);
+    let losses = import
+        .precision_loss()
+        .iter()
+        .map(|loss| (loss.lower(), loss.upper(), loss.count()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        losses,
+        [(f64::NEG_INFINITY, 1.1, 2), (1.2, f64::INFINITY, 2)]
+    );
+}
+
+#[test]
+fn from_prometheus_errors() {
+    use circllhist::{BucketDistribution, PrometheusParseError};
+
+    let err = |text: &str| {
+        Histogram::from_prometheus(text, "x", &[], BucketDistribution::Uniform).unwrap_err()
+    };
+    assert_eq!(err(""), PrometheusParseError::NotFound);
+    assert_eq!(
+        err("y_bucket{le=\"+Inf\"} 1"),
+        PrometheusParseError::NotFound
+    );
+    assert_eq!(
+        err("x_count 1\nx_bucket{le=\"1\"} 1"),
+        PrometheusParseError::MissingInfBucket
+    );
+    assert_eq!(
+        err("x_bucket{le=\"1\"} 2\nx_bucket{le=\"+Inf\"} 1"),
+        PrometheusParseError::NotCumulative { le: f64::INFINITY }
+    );
+    assert_eq!(
+        err("x_bucket{le=\"1\"} 1\nx_bucket{le=\"1.0\"} 1\nx_bucket{le=\"+Inf\"} 1"),
+        PrometheusParseError::DuplicateBucket { le: 1.0 }
+    );
+    assert_eq!(
+        err("x_bucket{le=\"+Inf\"} 2\nx_count 3"),
+        PrometheusParseError::CountMismatch {
+            buckets: 2,
+            count: 3
+        }
+    );
+    assert_eq!(
+        err("\nx_bucket{a=\"b\"} 2"),
+        PrometheusParseError::MissingLe { line: 2 }
+    );
+    assert_eq!(
+        err("x_bucket{le=\"+Inf\"} 1.5"),
+        PrometheusParseError::InvalidValue {
+            line: 1,
+            value: "1.5".to_string()
+        }
+    );
+    assert_eq!(
+        err("x_bucket{le=\"NaN\"} 1"),
+        PrometheusParseError::InvalidValue {
+            line: 1,
+            value: "NaN".to_string()
+        }
+    );
+    for (text, expected) in [
+        ("x_bucket{le=\"+Inf} 1", "`\"`"),
+        ("x_bucket{le=+Inf} 1", "`\"`"),
+        ("x_bucket{le=\"+Inf\" a=\"b\"} 1", "`,` or `}`"),
+        ("x_bucket{le=\"\\t\"} 1", "an escape sequence"),
+        ("x_bucket{1a=\"b\"} 1", "a label name"),
+        ("x_bucket{le=\"+Inf\"}", "a space"),
+    ] {
+        assert_eq!(
+            err(text),
+            PrometheusParseError::Syntax { line: 1, expected },
+            "{text}"
+        );
+    }
+}
@@ -0,0 +1,103 @@
+use circllhist::Histogram;
+
+const S1: &[f64] = &[0.123, 0.0, 0.43, 0.41, 0.415, 0.2201, 0.3201, 0.125, 0.13];
+const STRINGS: &[&str] = &[
+    "H[0.0e+00]=1",
+    "H[1.2e-01]=2",
+    "H[1.3e-01]=1",
+    "H[2.2e-01]=1",
+    "H[3.2e-01]=1",
+    "H[4.1e-01]=2",
+    "H[4.3e-01]=1",
+];
+
+fn s1_histogram() -> Histogram {
+    let mut histogram = Histogram::default();
+    for sample in S1 {
+        histogram
+            .record(*sample)
+            .expect("value should be recorded successfully");
+    }
+    histogram
+}
+
+#[test]
+fn json_is_bin_strings() {
+    let histogram = s1_histogram();
+    let json = serde_json::to_string(&histogram).expect("histogram should serialize");
+    let expected = serde_json::to_string(STRINGS).unwrap();
+    assert_eq!(json, expected);
+
+    let deserialized: Histogram =
+        serde_json::from_str(&json).expect("histogram should deserialize");
+    assert_eq!(histogram, deserialized);
+}
+
+#[test]
+fn json_large_values() {
+    let mut histogram = Histogram::default();
+    histogram.record(1.0).unwrap();
+    histogram.record_f64s(5.5e20, 3).unwrap();
+    histogram.record_f64s(1.7e-100, 4).unwrap();
+
+    let json = serde_json::to_string(&histogram).expect("histogram should serialize");
+    assert_eq!(json, r#"["H[1.7e-100]=4","H[1.0e+00]=1","H[5.5e+20]=3"]"#);
+    let deserialized: Histogram =
+        serde_json::from_str(&json).expect("histogram should deserialize");
+    assert_eq!(histogram, deserialized);
+}
+
+#[test]
+fn json_coalesces_bins() {
+    let json = serde_json::to_string(&[STRINGS, STRINGS].concat()).unwrap();
+    let doubled: Histogram = serde_json::from_str(&json).expect("histogram should deserialize");
+
+    let mut expected = Histogram::default();
+    for sample in S1 {
+        expected.record_f64s(*sample, 2).unwrap();
+    }
+    assert_eq!(doubled, expected);
+}
+
+#[test]
+fn json_invalid_bins() {
+    for json in [
+        r#"["H[1.2e-01]"]"#,
+        r#"["H[1.2e-01]=-2"]"#,
+        r#"["1.2e-01=2"]"#,
+        r#"["H[lol]=2"]"#,
+        r#"[12]"#,
+        r#""H[1.2e-01]=2""#,
+    ] {
+        let result = serde_json::from_str::<Histogram>(json);
+        assert!(result.is_err(), "{json} should not deserialize: {result:?}");
+    }
+
+    let error = serde_json::from_str::<Histogram>(r#"["H[1.2e-01]=-2"]"#).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "invalid bin `H[1.2e-01]=-2`: bin count must not be negative at line 1 column 16"
+    );
+}
+
+#[test]
+fn bincode_roundtrip() {
+    let mut histogram = s1_histogram();
+    histogram.record_f64s(5.5e20, i64::MAX).unwrap();
+
+    let bytes = bincode::serialize(&histogram).expect("histogram should serialize");
+    // a length prefix, then two bytes of bin and eight bytes of count per
+    // bucket.
+    assert_eq!(bytes.len(), 8 + 8 * 10);
+    let deserialized: Histogram =
+        bincode::deserialize(&bytes).expect("histogram should deserialize");
+    assert_eq!(histogram, deserialized);
+}
+
+#[test]
+fn bincode_invalid_bins() {
+    // a zero bin with a non-zero exponent
+    let bytes = bincode::serialize(&vec![(0i8, 3i8, 1u64)]).unwrap();
+    let error = bincode::deserialize::<Histogram>(&bytes).unwrap_err();
+    assert_eq!(error.to_string(), "invalid bin (val: 0, exp: 3)");
+}
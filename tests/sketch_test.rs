@@ -0,0 +1,196 @@
+use circllhist::Histogram;
+
+#[test]
+fn ddsketch() {
+    use circllhist::DDSketch;
+    use std::collections::BTreeMap;
+
+    let mut h = Histogram::new();
+    for val in [0.0, 1.0, 1.0, 2.5, -30.0, f64::NAN] {
+        h.record(val).unwrap();
+    }
+    let sketch = h.to_ddsketch(0.01);
+    assert_eq!(sketch.gamma, 1.01 / 0.99);
+    assert_eq!(sketch.index_offset, 0);
+    assert!((sketch.relative_accuracy() - 0.01).abs() < 1e-12);
+    assert_eq!(sketch.zero_count, 1);
+    // 1.05, 2.55 and 30.5 are in buckets 3, 47 and 171.
+    assert_eq!(sketch.positive, BTreeMap::from([(3, 2), (47, 1)]));
+    assert_eq!(sketch.negative, BTreeMap::from([(171, 1)]));
+
+    let roundtrip = Histogram::try_from(&sketch).unwrap();
+    assert_eq!(
+        roundtrip.dec_strings(),
+        [
+            "H[-3.0e+01]=1",
+            "H[0.0e+00]=1",
+            "H[1.0e+00]=2",
+            "H[2.5e+00]=1"
+        ]
+    );
+
+    // shifting every index along with the offset gives the same buckets.
+    let shift = |store: &BTreeMap<i32, u64>| store.iter().map(|(&i, &c)| (i - 5, c)).collect();
+    let shifted = DDSketch {
+        index_offset: -5,
+        positive: shift(&sketch.positive),
+        negative: shift(&sketch.negative),
+        ..sketch.clone()
+    };
+    assert_eq!(Histogram::try_from(&shifted).unwrap(), roundtrip);
+}
+
+#[test]
+fn ddsketch_matches_sketches_ddsketch() {
+    use sketches_ddsketch::Config;
+    use std::collections::BTreeMap;
+
+    // values in the middle of their bins, so each is its bin's midpoint.
+    let values = [1.05, 2.55, 30.5, 0.0125, 4.55e6, 7.25e-5, 9.95e-9];
+    let mut h = Histogram::new();
+    for val in values {
+        h.record(val).unwrap();
+        h.record(-val).unwrap();
+    }
+
+    let config = Config::defaults();
+    let sketch = h.to_ddsketch(0.01);
+    assert!((sketch.gamma - config.gamma).abs() < 1e-12);
+    let keys: BTreeMap<i32, u64> = values.iter().map(|&val| (config.key(val), 1)).collect();
+    assert_eq!(sketch.positive, keys);
+    assert_eq!(sketch.negative, keys);
+
+    // both sketches approximate each value to within 1%, so they agree to
+    // within 2%.
+    for val in values {
+        let mut reference = sketches_ddsketch::DDSketch::new(config);
+        reference.add(val);
+        let expected = reference.quantile(0.5).unwrap().unwrap();
+        let mut h = Histogram::new();
+        h.record(val).unwrap();
+        let actual = Histogram::try_from(&h.to_ddsketch(0.01))
+            .unwrap()
+            .quantile(0.5)
+            .unwrap();
+        assert!(
+            (actual - expected).abs() <= val * 0.02,
+            "{val}: {actual} vs {expected}"
+        );
+    }
+}
+
+#[test]
+fn tdigest() {
+    use circllhist::{Centroid, TDigest};
+
+    let mut h = Histogram::new();
+    for val in [-2.0, 1.0, 1.0, 1.15, 5.0] {
+        h.record(val).unwrap();
+    }
+    let digest = h.to_tdigest();
+    let expected = [(-2.05, 1), (1.05, 2), (1.15, 1), (5.05, 1)];
+    assert_eq!(digest.centroids.len(), expected.len());
+    for (centroid, (mean, weight)) in digest.centroids.iter().zip(expected) {
+        assert!((centroid.mean - mean).abs() < 1e-12, "{centroid:?}");
+        assert_eq!(centroid.weight, weight);
+    }
+    assert!((digest.min + 2.1).abs() < 1e-12, "{}", digest.min);
+    assert!((digest.max - 5.1).abs() < 1e-12, "{}", digest.max);
+    let roundtrip = Histogram::try_from(&digest).unwrap();
+    assert_eq!(roundtrip, h);
+
+    // the centroid of 4 values reaches halfway to its neighbours, and the
+    // values are spread over the 2 bins it overlaps.
+    let digest = TDigest {
+        centroids: vec![
+            Centroid {
+                mean: 1.0,
+                weight: 1,
+            },
+            Centroid {
+                mean: 1.2,
+                weight: 4,
+            },
+            Centroid {
+                mean: 1.3,
+                weight: 1,
+            },
+        ],
+        min: f64::NAN,
+        max: f64::NAN,
+    };
+    let h = Histogram::try_from(&digest).unwrap();
+    assert_eq!(
+        h.dec_strings(),
+        [
+            "H[1.0e+00]=1",
+            "H[1.1e+00]=2",
+            "H[1.2e+00]=2",
+            "H[1.3e+00]=1"
+        ]
+    );
+}
+
+#[test]
+fn sketch_errors() {
+    use circllhist::{Centroid, DDSketch, DDSketchError, TDigest, TDigestError};
+    use std::collections::BTreeMap;
+
+    let sketch = DDSketch {
+        gamma: 1.0,
+        index_offset: 0,
+        zero_count: 0,
+        positive: BTreeMap::new(),
+        negative: BTreeMap::new(),
+    };
+    assert_eq!(
+        Histogram::try_from(&sketch),
+        Err(DDSketchError::InvalidGamma(1.0))
+    );
+
+    // the lower bound of the smallest bucket used to overflow its index.
+    let sketch = DDSketch {
+        gamma: 1.02,
+        index_offset: 0,
+        zero_count: 0,
+        positive: BTreeMap::from([(i32::MIN, 1)]),
+        negative: BTreeMap::from([(i32::MIN, 1)]),
+    };
+    assert_eq!(
+        Histogram::try_from(&sketch).unwrap().dec_strings(),
+        ["H[0.0e+00]=2"]
+    );
+
+    let centroids = vec![
+        Centroid {
+            mean: 2.0,
+            weight: 1,
+        },
+        Centroid {
+            mean: 1.0,
+            weight: 1,
+        },
+    ];
+    let mut digest = TDigest {
+        centroids,
+        min: f64::NAN,
+        max: f64::NAN,
+    };
+    assert_eq!(
+        Histogram::try_from(&digest),
+        Err(TDigestError::InvalidMean { index: 1 })
+    );
+    digest.centroids.swap(0, 1);
+    digest.min = 1.5;
+    digest.max = 2.5;
+    assert_eq!(
+        Histogram::try_from(&digest),
+        Err(TDigestError::InvalidRange { min: 1.5, max: 2.5 })
+    );
+}
+
+#[test]
+#[should_panic(expected = "invalid DDSketch relative accuracy")]
+fn ddsketch_invalid_relative_accuracy() {
+    let _ = Histogram::new().to_ddsketch(1.0);
+}
@@ -45,9 +45,25 @@ pub(crate) fn test_quantiles<const QS: usize>(
     vals: &[f64],
     quantiles: [f64; QS],
     expected_quantiles: [f64; QS],
+) {
+    test_quantiles_with(
+        circllhist::QuantileMethod::Type1,
+        vals,
+        quantiles,
+        expected_quantiles,
+    )
+}
+
+#[track_caller]
+#[allow(dead_code)]
+pub(crate) fn test_quantiles_with<const QS: usize>(
+    method: circllhist::QuantileMethod,
+    vals: &[f64],
+    quantiles: [f64; QS],
+    expected_quantiles: [f64; QS],
 ) {
     let mut histogram = circllhist::Histogram::default();
-    eprintln!("--- quantiles: {quantiles:?} ---");
+    eprintln!("--- {method:?} quantiles: {quantiles:?} ---");
     eprintln!("    vals: {vals:?}");
 
     for sample in vals {
@@ -59,7 +75,7 @@ pub(crate) fn test_quantiles<const QS: usize>(
     eprintln!("expected: {expected_quantiles:?}");
 
     let actual_quantiles = histogram
-        .approx_quantiles(&quantiles)
+        .approx_quantiles_with(method, &quantiles)
         .expect("quantiles should be calculated successfully");
     eprintln!("  actual: {actual_quantiles:?}");
 
@@ -76,6 +92,123 @@ pub(crate) fn test_quantiles<const QS: usize>(
     eprintln!("--- ok!\n");
 }
 
+/// Asserts that `text` is a single Prometheus histogram family in the text
+/// exposition format: that every line matches the format's grammar, and that
+/// the `_bucket` series are cumulative, with increasing `le` boundaries
+/// ending at `+Inf`, whose count matches `_count`.
+///
+/// Returns the `(le, count)` pairs of the `_bucket` series.
+#[allow(dead_code)]
+pub fn assert_prometheus_histogram(text: &str, name: &str) -> Vec<(f64, u64)> {
+    fn is_name(s: &str, colons: bool) -> bool {
+        let valid = |c: char| c.is_ascii_alphanumeric() || c == '_' || (colons && c == ':');
+        !s.is_empty() && !s.starts_with(|c: char| c.is_ascii_digit()) && s.chars().all(valid)
+    }
+    fn parse_value(s: &str) -> f64 {
+        match s {
+            "+Inf" => f64::INFINITY,
+            "-Inf" => f64::NEG_INFINITY,
+            "NaN" => f64::NAN,
+            s => {
+                assert!(
+                    !s.contains("inf") && !s.contains("NaN"),
+                    "invalid float `{s}`"
+                );
+                s.parse()
+                    .unwrap_or_else(|e| panic!("invalid float `{s}`: {e}"))
+            }
+        }
+    }
+
+    assert!(text.ends_with('\n'), "exposition must end with a newline");
+    let mut lines = text.lines();
+    assert_eq!(lines.next(), Some(&*format!("# TYPE {name} histogram")));
+
+    let mut buckets = Vec::new();
+    let mut series_labels = None;
+    let mut count = None;
+    for line in lines {
+        assert!(!line.starts_with('#'), "unexpected comment `{line}`");
+        let (series, value) = line.rsplit_once(' ').expect("sample has no value");
+        let (metric, labels) = match series.split_once('{') {
+            Some((metric, labels)) => (
+                metric,
+                labels.strip_suffix('}').expect("unterminated label set"),
+            ),
+            None => (series, ""),
+        };
+        assert!(is_name(metric, true), "invalid metric name `{metric}`");
+
+        // label pairs, with `\\`, `\"` and `\n` the only escapes in values.
+        let mut le = None;
+        let mut other_labels = Vec::new();
+        let mut rest = labels;
+        while !rest.is_empty() {
+            let (label, value) = rest.split_once("=\"").expect("label has no value");
+            assert!(is_name(label, false), "invalid label name `{label}`");
+            let mut chars = value.char_indices();
+            let mut unescaped = String::new();
+            let end = loop {
+                match chars.next().expect("unterminated label value") {
+                    (_, '\\') => match chars.next().expect("unterminated escape") {
+                        (_, '\\') => unescaped.push('\\'),
+                        (_, '"') => unescaped.push('"'),
+                        (_, 'n') => unescaped.push('\n'),
+                        (_, c) => panic!("invalid escape `\\{c}`"),
+                    },
+                    (i, '"') => break i,
+                    (_, '\n') => panic!("unescaped newline in label value"),
+                    (_, c) => unescaped.push(c),
+                }
+            };
+            rest = &value[end + 1..];
+            if !rest.is_empty() {
+                rest = rest
+                    .strip_prefix(',')
+                    .expect("labels must be comma-separated");
+            }
+            if label == "le" {
+                le = Some(parse_value(&unescaped));
+            } else {
+                other_labels.push((label.to_string(), unescaped));
+            }
+        }
+        assert_eq!(
+            *series_labels.get_or_insert_with(|| other_labels.clone()),
+            other_labels,
+            "all series must have the same labels"
+        );
+
+        let value = parse_value(value);
+        match metric.strip_prefix(name) {
+            Some("_bucket") => {
+                let le = le.expect("bucket has no `le` label");
+                assert!(count.is_none(), "bucket after `_count`");
+                if let Some(&(prev_le, prev_count)) = buckets.last() {
+                    assert!(le > prev_le, "`le` {le} not greater than {prev_le}");
+                    assert!(
+                        value as u64 >= prev_count,
+                        "bucket counts must be cumulative"
+                    );
+                }
+                assert_eq!(value.fract(), 0.0, "bucket counts must be integers");
+                buckets.push((le, value as u64));
+            }
+            Some("_sum") => assert!(le.is_none(), "`_sum` has an `le` label"),
+            Some("_count") => {
+                assert!(le.is_none(), "`_count` has an `le` label");
+                count = Some(value as u64);
+            }
+            _ => panic!("unexpected series `{metric}`"),
+        }
+    }
+
+    let &(last_le, inf_count) = buckets.last().expect("no buckets");
+    assert_eq!(last_le, f64::INFINITY, "last bucket must be `+Inf`");
+    assert_eq!(Some(inf_count), count, "`+Inf` bucket must match `_count`");
+    buckets
+}
+
 #[test]
 fn approx_eq_succeeds() {
     assert_approx_eq!(0.1 + 0.2, 0.3);